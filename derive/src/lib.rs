@@ -0,0 +1,598 @@
+//! This crate provides `#[derive(Diagnostic)]` for the `Diagnostic` trait of
+//! `bittongue`. Use it through the `derive` feature of `bittongue`, which
+//! re-exports it as `bittongue::diagnostic::Diagnostic`.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use bittongue::{diagnostic::Diagnostic, source::Span};
+//!
+//! #[derive(Debug, Diagnostic)]
+//! #[diagnostic(error, code = "E0003", message = "unmatched `{open}`")]
+//! struct UnmatchedOpenParen {
+//!     #[primary(label = "opened here")]
+//!     open: Span,
+//!     #[secondary(label = "expected `)` before this")]
+//!     #[help = "close `{open}` here"]
+//!     end: Option<Span>,
+//!     #[note]
+//!     context: Option<String>,
+//! }
+//! ```
+//!
+//! # Container attribute
+//!
+//! `#[diagnostic(...)]` is placed on the struct, and accepts:
+//! - `bug`, `error`, `warning`, `note` or `help`: the level of the diagnostic
+//!   (required);
+//! - `code = "..."`: the code of the diagnostic;
+//! - `message = "..."`: the message of the diagnostic. `Display` is derived
+//!   from it, otherwise it must be implemented by hand.
+//!
+//! # Field attributes
+//!
+//! - `#[primary]` or `#[primary(label = "...")]`: the primary span, on at
+//!   most one field;
+//! - `#[secondary]` or `#[secondary(label = "...")]`: secondary spans;
+//! - `#[note = "..."]` or `#[help = "..."]`: a note or help child for each
+//!   span of the field;
+//! - `#[note]` or `#[help]`: a note or help child whose message is the value
+//!   of the field, if any.
+//!
+//! Fields with spans must implement `bittongue::diagnostic::SpanField` (e.g.
+//! `Span`, `Option<Span>` and `Vec<Span>`), and fields with messages must
+//! implement `bittongue::diagnostic::MessageField` (e.g. `String` and
+//! `Option<String>`).
+//!
+//! Messages and labels are format strings whose arguments name fields of the
+//! struct, e.g. `{open}` or `{open:?}`. Fields of type `Span` are formatted
+//! as their text in the source code. Other fields holding spans, such as
+//! `Option<Span>`, can only be formatted with `Debug`, e.g. `{end:?}`.
+//!
+//! The derive only accepts structs. Enums are rejected: derive it for a
+//! struct per variant instead.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input,
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Attribute,
+    Data,
+    DataStruct,
+    DeriveInput,
+    Error,
+    Expr,
+    ExprLit,
+    Fields,
+    GenericArgument,
+    Ident,
+    Lit,
+    LitStr,
+    Meta,
+    PathArguments,
+    Token,
+    Type,
+};
+
+/// Derives `Diagnostic`, and `Display` if a message is given. See the crate
+/// documentation for the attributes accepted.
+#[proc_macro_derive(
+    Diagnostic,
+    attributes(diagnostic, primary, secondary, note, help)
+)]
+pub fn derive_diagnostic(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+/// Options of the `#[diagnostic(...)]` attribute.
+#[derive(Default)]
+struct Container {
+    /// Variant of `Level`.
+    level: Option<Ident>,
+    /// Code of the diagnostic.
+    code: Option<LitStr>,
+    /// Message of the diagnostic, used to derive `Display`.
+    message: Option<LitStr>,
+}
+
+/// Role of a field given by one of its attributes.
+enum Role {
+    /// `#[primary]`, with an optional label.
+    Primary(Option<LitStr>),
+    /// `#[secondary]`, with an optional label.
+    Secondary(Option<LitStr>),
+    /// `#[note = "..."]` or `#[help = "..."]`: one child per span.
+    SpanChild(Ident, LitStr),
+    /// Bare `#[note]` or `#[help]`: a child with the field's message.
+    MessageChild(Ident),
+}
+
+/// A named field of the struct.
+struct Field<'input> {
+    /// Name of the field.
+    ident: &'input Ident,
+    /// Type of the field.
+    ty: &'input Type,
+}
+
+/// Expands the derive for the given input.
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let container = parse_container(input)?;
+    let empty = Punctuated::<syn::Field, Token![,]>::new();
+    let named = match &input.data {
+        Data::Struct(DataStruct { fields: Fields::Named(fields), .. }) => {
+            &fields.named
+        },
+        Data::Struct(DataStruct { fields: Fields::Unit, .. }) => &empty,
+        Data::Enum(data) => {
+            return Err(Error::new_spanned(
+                data.enum_token,
+                "`Diagnostic` cannot be derived for enums, derive it for a \
+                 struct per variant instead",
+            ))
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "`Diagnostic` can only be derived for structs with named \
+                 fields",
+            ))
+        },
+    };
+
+    let mut fields = Vec::new();
+    let mut roles = Vec::new();
+    for field in named {
+        let ident = field.ident.as_ref().expect("fields are named");
+        fields.push(Field { ident, ty: &field.ty });
+        for attr in &field.attrs {
+            if let Some(role) = parse_role(attr)? {
+                roles.push((ident, role));
+            }
+        }
+    }
+
+    let krate = quote!(::bittongue::diagnostic);
+    let mut primary = None;
+    let mut secondary = Vec::new();
+    let mut labels = Vec::new();
+    let mut children = Vec::new();
+    for (ident, role) in &roles {
+        match role {
+            Role::Primary(label) | Role::Secondary(label) => {
+                let is_primary = matches!(role, Role::Primary(_));
+                let constructor = if is_primary {
+                    if primary.is_some() {
+                        return Err(Error::new_spanned(
+                            ident,
+                            "only one field can be `#[primary]`",
+                        ));
+                    }
+                    primary = Some(*ident);
+                    quote!(primary)
+                } else {
+                    secondary.push(*ident);
+                    quote!(secondary)
+                };
+                let message = match label {
+                    Some(label) => {
+                        let args = format_args(label, &fields)?;
+                        quote!(.with_message(::std::format!(#args)))
+                    },
+                    None => quote!(),
+                };
+                labels.push(quote! {
+                    for span in #krate::SpanField::spans(&self.#ident) {
+                        labels.push(
+                            #krate::Label::#constructor(span) #message
+                        );
+                    }
+                });
+            },
+            Role::SpanChild(kind, message) => {
+                let args = format_args(message, &fields)?;
+                children.push(quote! {
+                    for span in #krate::SpanField::spans(&self.#ident) {
+                        children.push(
+                            #krate::Child::#kind(::std::format!(#args))
+                                .with_span(span)
+                        );
+                    }
+                });
+            },
+            Role::MessageChild(kind) => {
+                children.push(quote! {
+                    if let ::std::option::Option::Some(message) =
+                        #krate::MessageField::message(&self.#ident)
+                    {
+                        children.push(#krate::Child::#kind(message));
+                    }
+                });
+            },
+        }
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+    let level = container.level;
+
+    let primary_span = match primary {
+        Some(ident) => quote! {
+            #krate::SpanField::spans(&self.#ident).into_iter().next()
+        },
+        None => quote!(::std::option::Option::None),
+    };
+
+    let code = container.code.map(|code| {
+        quote! {
+            fn code(&self) -> ::std::option::Option<&str> {
+                ::std::option::Option::Some(#code)
+            }
+        }
+    });
+
+    let secondary_spans = if secondary.is_empty() {
+        None
+    } else {
+        Some(quote! {
+            fn secondary_spans<'this>(
+                &'this self,
+            ) -> ::std::option::Option<::std::boxed::Box<
+                dyn ::std::iter::Iterator<Item = ::bittongue::source::Span>
+                    + ::std::marker::Send
+                    + ::std::marker::Sync
+                    + 'this,
+            >> {
+                let mut spans = ::std::vec::Vec::new();
+                #(spans.extend(#krate::SpanField::spans(&self.#secondary));)*
+                ::std::option::Option::Some(
+                    ::std::boxed::Box::new(spans.into_iter())
+                )
+            }
+        })
+    };
+
+    let labels = if labels.is_empty() {
+        None
+    } else {
+        Some(quote! {
+            fn labels<'this>(
+                &'this self,
+            ) -> ::std::boxed::Box<
+                dyn ::std::iter::Iterator<Item = #krate::Label>
+                    + ::std::marker::Send
+                    + ::std::marker::Sync
+                    + 'this,
+            > {
+                let mut labels = ::std::vec::Vec::new();
+                #(#labels)*
+                ::std::boxed::Box::new(labels.into_iter())
+            }
+        })
+    };
+
+    let children = if children.is_empty() {
+        None
+    } else {
+        Some(quote! {
+            fn children<'this>(
+                &'this self,
+            ) -> ::std::boxed::Box<
+                dyn ::std::iter::Iterator<Item = #krate::Child>
+                    + ::std::marker::Send
+                    + ::std::marker::Sync
+                    + 'this,
+            > {
+                let mut children = ::std::vec::Vec::new();
+                #(#children)*
+                ::std::boxed::Box::new(children.into_iter())
+            }
+        })
+    };
+
+    let display = match &container.message {
+        Some(message) => {
+            let args = format_args(message, &fields)?;
+            Some(quote! {
+                impl #impl_generics ::std::fmt::Display for #name #ty_generics
+                #where_clause
+                {
+                    fn fmt(
+                        &self,
+                        fmtr: &mut ::std::fmt::Formatter<'_>,
+                    ) -> ::std::fmt::Result {
+                        ::std::write!(fmtr, #args)
+                    }
+                }
+            })
+        },
+        None => None,
+    };
+
+    Ok(quote! {
+        impl #impl_generics #krate::Diagnostic for #name #ty_generics
+        #where_clause
+        {
+            fn level(&self) -> #krate::Level {
+                #krate::Level::#level
+            }
+
+            fn primary_span(
+                &self,
+            ) -> ::std::option::Option<::bittongue::source::Span> {
+                #primary_span
+            }
+
+            #code
+            #secondary_spans
+            #labels
+            #children
+        }
+
+        #display
+    })
+}
+
+/// Parses the `#[diagnostic(...)]` attributes of the struct.
+fn parse_container(input: &DeriveInput) -> syn::Result<Container> {
+    let mut container = Container::default();
+    for attr in &input.attrs {
+        if !attr.path().is_ident("diagnostic") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            let level = if meta.path.is_ident("bug") {
+                Some("Bug")
+            } else if meta.path.is_ident("error") {
+                Some("Error")
+            } else if meta.path.is_ident("warning") {
+                Some("Warning")
+            } else if meta.path.is_ident("note") {
+                Some("Note")
+            } else if meta.path.is_ident("help") {
+                Some("Help")
+            } else {
+                None
+            };
+            if let Some(level) = level {
+                if container.level.is_some() {
+                    return Err(meta.error("level given more than once"));
+                }
+                container.level = Some(Ident::new(level, meta.path.span()));
+            } else if meta.path.is_ident("code") {
+                container.code = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("message") {
+                container.message = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error(
+                    "expected `bug`, `error`, `warning`, `note`, `help`, \
+                     `code` or `message`",
+                ));
+            }
+            Ok(())
+        })?;
+    }
+    if container.level.is_none() {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "missing level, e.g. `#[diagnostic(error)]`",
+        ));
+    }
+    Ok(container)
+}
+
+/// Parses an attribute of a field into its role, if the attribute belongs to
+/// this derive.
+fn parse_role(attr: &Attribute) -> syn::Result<Option<Role>> {
+    let path = attr.path();
+    if path.is_ident("primary") || path.is_ident("secondary") {
+        let mut label = None;
+        match &attr.meta {
+            Meta::Path(_) => (),
+            Meta::List(_) => attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("label") {
+                    label = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `label = \"...\"`"))
+                }
+            })?,
+            Meta::NameValue(_) => {
+                return Err(Error::new_spanned(
+                    attr,
+                    "expected `label = \"...\"` in parentheses",
+                ))
+            },
+        }
+        if path.is_ident("primary") {
+            Ok(Some(Role::Primary(label)))
+        } else {
+            Ok(Some(Role::Secondary(label)))
+        }
+    } else if path.is_ident("note") || path.is_ident("help") {
+        let kind = path.get_ident().expect("path is an ident").clone();
+        match &attr.meta {
+            Meta::Path(_) => Ok(Some(Role::MessageChild(kind))),
+            Meta::NameValue(meta) => match &meta.value {
+                Expr::Lit(ExprLit { lit: Lit::Str(message), .. }) => {
+                    Ok(Some(Role::SpanChild(kind, message.clone())))
+                },
+                value => {
+                    Err(Error::new_spanned(value, "expected a string literal"))
+                },
+            },
+            Meta::List(_) => Err(Error::new_spanned(
+                attr,
+                format!("expected `#[{0}]` or `#[{0} = \"...\"]`", kind),
+            )),
+        }
+    } else {
+        Ok(None)
+    }
+}
+
+/// Builds the arguments of `format!` for the given format string, naming
+/// the fields it refers to.
+fn format_args(
+    message: &LitStr,
+    fields: &[Field],
+) -> syn::Result<TokenStream2> {
+    let value = message.value();
+    let mut names = Vec::<(String, bool)>::new();
+    let mut chars = value.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '{' {
+            continue;
+        }
+        if chars.peek() == Some(&'{') {
+            chars.next();
+            continue;
+        }
+        let mut argument = String::new();
+        loop {
+            match chars.next() {
+                Some('}') => break,
+                Some(ch) => argument.push(ch),
+                None => {
+                    return Err(Error::new_spanned(message, "unclosed `{`"))
+                },
+            }
+        }
+        let (name, spec) = argument.split_once(':').unwrap_or((&argument, ""));
+        let name = name.trim();
+        if name.is_empty() || name.starts_with(|ch: char| ch.is_ascii_digit()) {
+            return Err(Error::new_spanned(
+                message,
+                "arguments must name fields, e.g. `{span}`",
+            ));
+        }
+        let display = !spec.ends_with('?');
+        match names.iter_mut().find(|(known, _)| known == name) {
+            Some((_, displayed)) => *displayed |= display,
+            None => names.push((name.to_owned(), display)),
+        }
+    }
+
+    let mut args = Vec::new();
+    for (name, display) in &names {
+        let field = fields
+            .iter()
+            .find(|field| field.ident == name)
+            .ok_or_else(|| {
+                Error::new_spanned(
+                    message,
+                    format!("no field named `{}`", name),
+                )
+            })?;
+        let ident = field.ident;
+        if *display && !is_span(field.ty) && holds_span(field.ty) {
+            return Err(Error::new_spanned(
+                field.ty,
+                format!(
+                    "only fields of type `Span` can be formatted as their \
+                     text, use `{{{0}:?}}` to format `{0}` with `Debug`",
+                    name,
+                ),
+            ));
+        }
+        if is_span(field.ty) {
+            args.push(quote!(#ident = self.#ident.as_str()));
+        } else {
+            args.push(quote!(#ident = self.#ident));
+        }
+    }
+    Ok(quote!(#message #(, #args)*))
+}
+
+/// Tests whether the given type is `Span`, whatever its path.
+fn is_span(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => {
+            path.path.segments.last().is_some_and(|segment| {
+                segment.ident == "Span" && segment.arguments.is_empty()
+            })
+        },
+        _ => false,
+    }
+}
+
+/// Tests whether the given type has `Span` among its generic arguments, at
+/// any depth, e.g. `Option<Span>` or `Vec<Span>`.
+fn holds_span(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path.path.segments.iter().any(|segment| {
+            match &segment.arguments {
+                PathArguments::AngleBracketed(arguments) => {
+                    arguments.args.iter().any(|argument| match argument {
+                        GenericArgument::Type(ty) => {
+                            is_span(ty) || holds_span(ty)
+                        },
+                        _ => false,
+                    })
+                },
+                _ => false,
+            }
+        }),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::expand;
+    use syn::{parse_quote, DeriveInput};
+
+    fn expand_error(input: DeriveInput) -> String {
+        expand(&input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn span_containers() {
+        let input = parse_quote! {
+            #[diagnostic(error, message = "expected `{found}`")]
+            struct Expected {
+                #[primary]
+                found: Option<Span>,
+            }
+        };
+        assert_eq!(
+            expand_error(input),
+            "only fields of type `Span` can be formatted as their text, use \
+             `{found:?}` to format `found` with `Debug`"
+        );
+
+        let input = parse_quote! {
+            #[diagnostic(error, message = "{found:?} or `{open}`")]
+            struct Expected {
+                #[primary]
+                open: Span,
+                #[secondary]
+                found: Vec<Span>,
+            }
+        };
+        assert!(expand(&input).is_ok());
+    }
+
+    #[test]
+    fn enums() {
+        let input = parse_quote! {
+            #[diagnostic(error)]
+            enum Syntax {
+                Unexpected(Span),
+                Unclosed(Span),
+            }
+        };
+        assert_eq!(
+            expand_error(input),
+            "`Diagnostic` cannot be derived for enums, derive it for a struct \
+             per variant instead"
+        );
+    }
+}
@@ -61,7 +61,7 @@ impl<'expr> fmt::Display for DisplayExpr<'expr> {
         // indentation for the span.
         write_indent(self.level, fmtr)?;
         // writes the span
-        write!(fmtr, "[{}]\n", self.expr.span)?;
+        writeln!(fmtr, "[{}]", self.expr.span)?;
         // delegates further rendering to the expression's kind.
         write!(
             fmtr,
@@ -88,7 +88,7 @@ impl<'expr> fmt::Display for DisplayExprKind<'expr> {
             ExprKind::Variable(name) => {
                 // Indentation for the variable name.
                 write_indent(self.level, fmtr)?;
-                write!(fmtr, "{}\n", name.as_str())
+                writeln!(fmtr, "{}", name.as_str())
             },
 
             // Renders an application.
@@ -106,7 +106,7 @@ impl<'expr> fmt::Display for DisplayExprKind<'expr> {
                 if fun_needs_parens {
                     // Advanced indentation for parenthesis.
                     write_indent(self.level + 1, fmtr)?;
-                    write!(fmtr, "(\n")?;
+                    writeln!(fmtr, "(")?;
                     // Renders function with two levels of indentation ahead.
                     write!(
                         fmtr,
@@ -115,7 +115,7 @@ impl<'expr> fmt::Display for DisplayExprKind<'expr> {
                     )?;
                     // Closes parenthesis.
                     write_indent(self.level + 1, fmtr)?;
-                    write!(fmtr, ")\n")?;
+                    writeln!(fmtr, ")")?;
                 } else {
                     // Renders function without parenthesis.
                     write!(
@@ -128,7 +128,7 @@ impl<'expr> fmt::Display for DisplayExprKind<'expr> {
                 if arg_needs_parens {
                     // Advanced indentation for parenthesis.
                     write_indent(self.level + 1, fmtr)?;
-                    write!(fmtr, "(\n")?;
+                    writeln!(fmtr, "(")?;
                     // Renders function with two levels of indentation ahead.
                     write!(
                         fmtr,
@@ -137,7 +137,7 @@ impl<'expr> fmt::Display for DisplayExprKind<'expr> {
                     )?;
                     // Closes parenthesis.
                     write_indent(self.level + 1, fmtr)?;
-                    write!(fmtr, ")\n")?;
+                    writeln!(fmtr, ")")?;
                 } else {
                     // Renders argument without parenthesis.
                     write!(
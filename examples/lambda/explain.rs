@@ -0,0 +1,33 @@
+//! Long-form explanations of the diagnostic codes, shown by `--explain`.
+
+use bittongue::diagnostic::Registry;
+
+/// Creates a registry with the explanation of every diagnostic code.
+pub fn registry() -> Registry {
+    let mut registry = Registry::new();
+    registry
+        .register(
+            "E0001",
+            "The source contains a character that is not part of the lambda \
+             calculus syntax.\n\nOnly ASCII letters, digits and underscores \
+             can form variable names, and the only punctuation is `\\`, `.`, \
+             `(` and `)`.",
+        )
+        .register(
+            "E0002",
+            "A token was found where the syntax does not allow it.\n\nA \
+             lambda is written as `\\parameter. body`, where the parameter is \
+             a single variable and the body is any expression.",
+        )
+        .register(
+            "E0003",
+            "An opening parenthesis `(` was never closed.\n\nEvery `(` must \
+             be matched by a `)` after the parenthesized expression.",
+        )
+        .register(
+            "E0004",
+            "A closing parenthesis `)` was found without a matching `(` \
+             before it.",
+        );
+    registry
+}
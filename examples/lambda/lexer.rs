@@ -1,4 +1,4 @@
-/// Exports items related to the lambda calculus lexer.
+//! Exports items related to the lambda calculus lexer.
 
 pub mod error;
 
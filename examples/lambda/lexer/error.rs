@@ -31,4 +31,8 @@ impl Diagnostic for InvalidGrapheme {
     fn primary_span(&self) -> Option<Span> {
         Some(self.span.clone())
     }
+
+    fn code(&self) -> Option<&str> {
+        Some("E0001")
+    }
 }
@@ -6,20 +6,29 @@ pub mod token;
 pub mod lexer;
 pub mod ast;
 pub mod parser;
+pub mod explain;
 
 use crate::{lexer::Lexer, parser::parse_expr};
 use bittongue::{
-    diagnostic::{Diagnostic, Diagnostics},
+    diagnostic::{Diagnostics, Renderer},
     lexer::TokenStream,
     source::Source,
 };
-use std::{env, fs, io, path::Path, process};
+use std::{
+    env,
+    io::{self, IsTerminal},
+    process,
+};
+
+/// Maximum number of errors shown.
+const ERROR_LIMIT: usize = 100;
 
 fn show_help() -> ! {
     eprintln!("Parses and show source code with AST nodes spans/locations");
     eprintln!("Usage:");
     eprintln!("    lambda --stdin                Reads source from stdin");
     eprintln!("    lambda -f FILEPATH            Reads source from file");
+    eprintln!("    lambda --explain CODE         Explains a diagnostic code");
     eprintln!("    lambda -h                     Shows this message and exits");
     process::exit(1);
 }
@@ -28,25 +37,30 @@ fn main() {
     let mut args = env::args_os();
     args.next();
 
-    let source_contents = match args.next() {
+    let loaded = match args.next() {
         Some(arg) if arg == "--stdin" => {
-            let mut buf = String::new();
-            match io::Read::read_to_string(&mut io::stdin(), &mut buf) {
-                Ok(_) => buf,
-                Err(error) => {
-                    eprintln!("stdin: {}", error);
-                    process::exit(1);
-                },
-            }
+            Source::from_reader("<stdin>", io::stdin())
         },
 
         Some(arg) if arg == "-f" => match args.next() {
-            Some(path_str) => match fs::read_to_string(&path_str) {
-                Ok(buf) => buf,
-                Err(error) => {
-                    eprintln!("{}: {}", Path::new(&path_str).display(), error);
-                    process::exit(1);
-                },
+            Some(path) => Source::from_path(path),
+            None => show_help(),
+        },
+
+        Some(arg) if arg == "--explain" => match args.next() {
+            Some(code) => {
+                let registry = explain::registry();
+                let code = code.to_string_lossy();
+                match registry.explain(&code) {
+                    Some(explanation) => {
+                        println!("{}", explanation);
+                        process::exit(0);
+                    },
+                    None => {
+                        eprintln!("{} is not a valid diagnostic code", code);
+                        process::exit(1);
+                    },
+                }
             },
             None => show_help(),
         },
@@ -56,21 +70,35 @@ fn main() {
         _ => show_help(),
     };
 
-    let source = Source::new("main.lam", source_contents);
+    let source = match loaded {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        },
+    };
     let mut diagnostics = Diagnostics::new();
-    let mut token_stream = TokenStream::new(&source, Lexer, &mut diagnostics);
+    diagnostics.set_error_limit(Some(ERROR_LIMIT));
 
-    let parse_result = parse_expr(&mut token_stream, &mut diagnostics);
+    // A panic in the parser is reported as an internal compiler error.
+    let parse_result = diagnostics.catch_ice(|diagnostics| {
+        let mut token_stream = TokenStream::new(&source, Lexer, diagnostics);
+        parse_expr(&mut token_stream, diagnostics)
+    });
 
-    for diagnostic in diagnostics {
-        eprint!("{}", diagnostic);
-        if let Some(span) = diagnostic.primary_span() {
-            eprint!(", {}", span);
-        }
-        eprintln!();
-    }
+    // Error recovery may complain more than once about the same token.
+    diagnostics.dedup();
+    diagnostics.sort();
+
+    let mut renderer = Renderer::new();
+    renderer.set_colored(io::stderr().is_terminal());
+    let mut rendered = String::new();
+    renderer
+        .write_all(&mut rendered, &diagnostics)
+        .expect("writing into a string cannot fail");
+    eprint!("{}", rendered);
 
-    if let Ok(expr) = parse_result {
+    if let Some(Ok(expr)) = parse_result {
         println!("{}", expr);
     }
 }
@@ -35,7 +35,7 @@ fn expect_token(
     token_stream: &mut TokenStream<Lexer>,
     diagnostics: &mut Diagnostics,
 ) -> Result<Token<TokenKind>, ParseError> {
-    let current = token_stream.current().map(Clone::clone);
+    let current = token_stream.current().cloned();
     match current {
         Ok(token) => {
             if expected.contains(&token.kind) {
@@ -101,8 +101,11 @@ fn parse_parenthesized(
             output_expr.span = output_expr.span.join(&token.span);
             token_stream.next(diagnostics);
         },
-        _ => {
-            let error = UnmatchedOpenParen { span: open_paren_token.span };
+        current => {
+            let error = UnmatchedOpenParen {
+                span: open_paren_token.span,
+                found: current.ok().map(|token| token.span.clone()),
+            };
             diagnostics.raise(error);
         },
     }
@@ -2,7 +2,7 @@
 
 use crate::token::TokenKind;
 use bittongue::{
-    diagnostic::{Diagnostic, Level},
+    diagnostic::{Child, Diagnostic, Level},
     lexer::Token,
     source::Span,
 };
@@ -53,52 +53,52 @@ impl Diagnostic for MismatchedToken {
     fn primary_span(&self) -> Option<Span> {
         Some(self.found.span.clone())
     }
+
+    fn code(&self) -> Option<&str> {
+        Some("E0002")
+    }
+
+    // Explain the lambda syntax when only the dot was expected.
+    fn children<'this>(
+        &'this self,
+    ) -> Box<dyn Iterator<Item = Child> + Send + Sync + 'this> {
+        let help = if self.expected == [TokenKind::Dot] {
+            Some(Child::help("a lambda is written as `\\parameter. body`"))
+        } else {
+            None
+        };
+        Box::new(help.into_iter())
+    }
 }
 
 /// Error raised when the parser expected a closing parenthesis matching an
 /// existing open parenthesis, but no such closing parenthesis was found.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Diagnostic)]
+#[diagnostic(
+    error,
+    code = "E0003",
+    message = "unmatched opening parenthesis `{span}`"
+)]
 pub struct UnmatchedOpenParen {
     /// Span of the opening parenthesis.
+    #[primary(label = "opening parenthesis here")]
     pub span: Span,
-}
-
-impl fmt::Display for UnmatchedOpenParen {
-    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmtr, "unmatched opening parenthesis `{}`", self.span.as_str())
-    }
-}
-
-impl Diagnostic for UnmatchedOpenParen {
-    fn level(&self) -> Level {
-        Level::Error
-    }
-
-    fn primary_span(&self) -> Option<Span> {
-        Some(self.span.clone())
-    }
+    /// Span of the token found where the closing parenthesis was expected, if
+    /// the lexer could produce it.
+    #[secondary(label = "expected `)` here")]
+    pub found: Option<Span>,
 }
 
 /// Error raised when the parser expected an opening parenthesis matching an
 /// existing closing parenthesis, but no such opening parenthesis was found.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Diagnostic)]
+#[diagnostic(
+    error,
+    code = "E0004",
+    message = "unmatched closing parenthesis `{span}`"
+)]
 pub struct UnmatchedCloseParen {
     /// Span of the closing parenthesis.
+    #[primary]
     pub span: Span,
 }
-
-impl fmt::Display for UnmatchedCloseParen {
-    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmtr, "unmatched closing parenthesis `{}`", self.span.as_str())
-    }
-}
-
-impl Diagnostic for UnmatchedCloseParen {
-    fn level(&self) -> Level {
-        Level::Error
-    }
-
-    fn primary_span(&self) -> Option<Span> {
-        Some(self.span.clone())
-    }
-}
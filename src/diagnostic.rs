@@ -1,10 +1,59 @@
 //! This module exports error-related utilities for parse/compile-time of a
 //! programming language.
 
-use crate::source::Span;
+mod catalog;
+mod child;
+mod emit;
+mod fields;
+mod html;
+mod ice;
+mod label;
+mod policy;
+mod registry;
+mod render;
+mod sink;
+mod suggestion;
+mod suppress;
+
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "sarif")]
+pub mod sarif;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+
+pub use catalog::{Catalog, Message, ResourceError, ResourceErrorKind};
+pub use child::{Child, ChildKind};
+pub use emit::{Emitter, GccEmitter, HumanEmitter};
+pub use fields::{MessageField, SpanField};
+pub use html::{HtmlPage, HtmlRenderer};
+pub use ice::{current_span, enter_span, InternalError, SpanGuard};
+pub use label::{Label, LabelStyle};
+pub use policy::{LintLevel, Policy};
+pub use registry::{Registry, RegistryIter};
+pub use render::{Rendered, Renderer};
+pub use sink::Sink;
+pub use suggestion::{Applicability, Suggestion};
+pub use suppress::{Suppression, Suppressions, UnusedSuppression};
+
+/// Derives [`Diagnostic`](trait@Diagnostic), and `Display` if a message is
+/// given. Only available with the `derive` feature.
+///
+/// See the documentation of the `bittongue-derive` crate for the attributes
+/// accepted.
+#[cfg(feature = "derive")]
+pub use bittongue_derive::Diagnostic;
+
+use crate::source::{Rewriter, Source, Span};
 use std::{
     any::{Any, TypeId},
+    collections::{HashMap, HashSet},
     fmt,
+    io,
+    iter,
+    marker::PhantomData,
+    mem,
+    panic::{self, AssertUnwindSafe},
     rc::Rc,
     slice,
     sync::Arc,
@@ -14,12 +63,30 @@ use std::{
 /// Level of a given diagnostic.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Level {
+    /// A help message, e.g. a hint on how to use the compiler.
+    Help,
     /// This is just a note, easily ignored.
     Note,
     /// This is a warning, should be read carefully.
     Warning,
     /// A hard error, cannot be ignored at all.
     Error,
+    /// An internal compiler error: a bug in the compiler itself, rather than
+    /// in the source code. Counts as a hard error, but is never omitted by
+    /// the error limit.
+    Bug,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        fmtr.write_str(match self {
+            Level::Help => "help",
+            Level::Note => "note",
+            Level::Warning => "warning",
+            Level::Error => "error",
+            Level::Bug => "internal compiler error",
+        })
+    }
 }
 
 /// Diagnostic: a problem or note found in a source code.
@@ -30,6 +97,26 @@ pub trait Diagnostic: fmt::Display + fmt::Debug + Any {
     /// Yields the primary span related to the diagnostic, if any.
     fn primary_span(&self) -> Option<Span>;
 
+    /// Stable code identifying the kind of this diagnostic (e.g. `E0042`),
+    /// if any. Codes are shown by renderers, can be explained through a
+    /// [`Registry`], and serve as keys to allow or deny diagnostics. By
+    /// default, there is no code.
+    fn code(&self) -> Option<&str> {
+        None
+    }
+
+    /// Localizable message of this diagnostic, if any. Renderers with a
+    /// [`Catalog`] translate it, falling back to the `Display` text when no
+    /// translation exists. By default, there is no localizable message.
+    ///
+    /// This replaces only the main message: label messages and
+    /// [children](Diagnostic::children) are not localizable. Machine-readable
+    /// outputs (the GCC, JSON, SARIF and LSP formats) always use the `Display`
+    /// text.
+    fn message(&self) -> Option<Message> {
+        None
+    }
+
     /// Yields an iterator over secondary spans related to the diagnostic, if
     /// any.
     fn secondary_spans<'this>(
@@ -37,6 +124,39 @@ pub trait Diagnostic: fmt::Display + fmt::Debug + Any {
     ) -> Option<Box<dyn Iterator<Item = Span> + Send + Sync + 'this>> {
         None
     }
+
+    /// Yields an iterator over the labeled spans of the diagnostic. Renderers
+    /// use labels to annotate each span with its own message.
+    ///
+    /// By default, yields a primary label for
+    /// [`primary_span`](Diagnostic::primary_span) and secondary labels for
+    /// [`secondary_spans`](Diagnostic::secondary_spans), all without
+    /// messages.
+    fn labels<'this>(
+        &'this self,
+    ) -> Box<dyn Iterator<Item = Label> + Send + Sync + 'this> {
+        let primary = self.primary_span().map(Label::primary);
+        let secondary =
+            self.secondary_spans().into_iter().flatten().map(Label::secondary);
+        Box::new(primary.into_iter().chain(secondary))
+    }
+
+    /// Yields an iterator over the child entries of the diagnostic, such as
+    /// notes and help messages, in the order they should be shown beneath
+    /// it. By default, there are no children.
+    fn children<'this>(
+        &'this self,
+    ) -> Box<dyn Iterator<Item = Child> + Send + Sync + 'this> {
+        Box::new(iter::empty())
+    }
+
+    /// Yields an iterator over the suggested fixes of the diagnostic. By
+    /// default, there are no suggestions.
+    fn suggestions<'this>(
+        &'this self,
+    ) -> Box<dyn Iterator<Item = Suggestion> + Send + Sync + 'this> {
+        Box::new(iter::empty())
+    }
 }
 
 macro_rules! impl_for_ptr {
@@ -53,11 +173,37 @@ macro_rules! impl_for_ptr {
                 (**self).primary_span()
             }
 
+            fn code(&self) -> Option<&str> {
+                (**self).code()
+            }
+
+            fn message(&self) -> Option<Message> {
+                (**self).message()
+            }
+
             fn secondary_spans<'this>(
                 &'this self,
             ) -> Option<Box<dyn Iterator<Item = Span> + Send + Sync + 'this>> {
                 (**self).secondary_spans()
             }
+
+            fn labels<'this>(
+                &'this self,
+            ) -> Box<dyn Iterator<Item = Label> + Send + Sync + 'this> {
+                (**self).labels()
+            }
+
+            fn children<'this>(
+                &'this self,
+            ) -> Box<dyn Iterator<Item = Child> + Send + Sync + 'this> {
+                (**self).children()
+            }
+
+            fn suggestions<'this>(
+                &'this self,
+            ) -> Box<dyn Iterator<Item = Suggestion> + Send + Sync + 'this> {
+                (**self).suggestions()
+            }
         }
     };
 }
@@ -107,13 +253,16 @@ where
 macro_rules! impl_downcast {
     ($ty:ty) => {
         impl $ty {
+            /// Returns whether the diagnostic is of type `T`.
             pub fn is<T>(&self) -> bool
             where
                 T: Diagnostic,
             {
-                Any::type_id(self) == TypeId::of::<Self>()
+                Any::type_id(self) == TypeId::of::<T>()
             }
 
+            /// Attempts to downcast the boxed diagnostic into type `T`,
+            /// giving it back on failure.
             pub fn downcast<T>(self: Box<Self>) -> Result<Box<T>, Box<Self>>
             where
                 T: Diagnostic,
@@ -126,6 +275,8 @@ macro_rules! impl_downcast {
                 }
             }
 
+            /// Returns a reference to the diagnostic as type `T`, if it is of
+            /// that type.
             pub fn downcast_ref<T>(&self) -> Option<&T>
             where
                 T: Diagnostic,
@@ -139,6 +290,8 @@ macro_rules! impl_downcast {
                 }
             }
 
+            /// Returns a mutable reference to the diagnostic as type `T`, if
+            /// it is of that type.
             pub fn downcast_mut<T>(&mut self) -> Option<&mut T>
             where
                 T: Diagnostic,
@@ -163,12 +316,87 @@ impl_downcast! { dyn Diagnostic + Send + Sync }
 /// A collection of diagnostics. Generic on the type of diagnostics, but
 /// intended for trait objects, such as `dyn Diagnostic` or `dyn Diagnostic +
 /// Send + Sync` (the last one is the default).
-#[derive(Debug)]
+///
+/// Raised diagnostics go through a [`Policy`], which may remap their levels or
+/// drop them. The collection keeps the effective level of each diagnostic.
+///
+/// An error limit can be set, after which further hard errors are counted but
+/// not stored. Lexers and parsers can check
+/// [`is_exhausted`](Diagnostics::is_exhausted) to stop early.
+///
+/// If created with an [`Emitter`], each diagnostic kept is also emitted as
+/// soon as it is raised. Diagnostics emitted that way cannot be taken back:
+/// [suppressions](Suppressions::apply), [`dedup`](Diagnostics::dedup),
+/// [`retain`](Diagnostics::retain) and [`sort`](Diagnostics::sort) only
+/// affect what is emitted afterwards. When streaming is turned off (see
+/// [`set_streaming`](Diagnostics::set_streaming)), diagnostics are instead
+/// emitted when the collection is [flushed](Diagnostics::flush), as they are
+/// by then.
 pub struct Diagnostics<D = dyn Diagnostic + Send + Sync>
 where
     D: Diagnostic + ?Sized,
 {
-    elements: Vec<Box<D>>,
+    /// Policy applied to raised diagnostics.
+    policy: Policy,
+    /// Diagnostics raised and kept, in order.
+    elements: Vec<Entry<D>>,
+    /// Maximum number of hard errors stored, if any.
+    error_limit: Option<usize>,
+    /// Number of hard errors stored.
+    errors: usize,
+    /// Number of hard errors raised after the limit was reached.
+    omitted: usize,
+    /// Emitter of raised diagnostics, if any.
+    emitter: Option<Box<dyn Emitter<D> + Send + Sync>>,
+    /// Whether diagnostics are emitted as soon as they are raised.
+    streaming: bool,
+    /// Number of omitted errors already reported to the emitter.
+    reported_omitted: usize,
+    /// First error the emitter failed with, if any.
+    emit_error: Option<io::Error>,
+}
+
+impl<D> fmt::Debug for Diagnostics<D>
+where
+    D: Diagnostic + ?Sized,
+{
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        fmtr.debug_struct("Diagnostics")
+            .field("policy", &self.policy)
+            .field("elements", &self.elements)
+            .field("error_limit", &self.error_limit)
+            .field("errors", &self.errors)
+            .field("omitted", &self.omitted)
+            .field("emitter", &self.emitter.as_ref().map(|_| ".."))
+            .field("streaming", &self.streaming)
+            .field("reported_omitted", &self.reported_omitted)
+            .field("emit_error", &self.emit_error)
+            .finish()
+    }
+}
+
+/// Fails to compile if the default [`Diagnostics`] stops being `Send + Sync`.
+const _: fn() = || {
+    fn assert_send_sync<T>()
+    where
+        T: Send + Sync,
+    {
+    }
+    assert_send_sync::<Diagnostics>();
+};
+
+/// A diagnostic kept in a [`Diagnostics`] collection.
+#[derive(Debug)]
+struct Entry<D>
+where
+    D: Diagnostic + ?Sized,
+{
+    /// Effective level of the diagnostic, after the policy is applied.
+    level: Level,
+    /// The diagnostic itself.
+    diagnostic: Box<D>,
+    /// Whether the diagnostic was passed to the emitter.
+    emitted: bool,
 }
 
 impl<D> Default for Diagnostics<D>
@@ -184,9 +412,141 @@ impl<D> Diagnostics<D>
 where
     D: Diagnostic + ?Sized,
 {
-    /// Creates an empty collection of diagnostics.
+    /// Creates an empty collection of diagnostics, keeping every diagnostic
+    /// at its own level.
     pub fn new() -> Self {
-        Self { elements: Vec::new() }
+        Self::with_policy(Policy::new())
+    }
+
+    /// Creates an empty collection of diagnostics with the given policy.
+    pub fn with_policy(policy: Policy) -> Self {
+        Self {
+            policy,
+            elements: Vec::new(),
+            error_limit: None,
+            errors: 0,
+            omitted: 0,
+            emitter: None,
+            streaming: true,
+            reported_omitted: 0,
+            emit_error: None,
+        }
+    }
+
+    /// Creates an empty collection of diagnostics that emits each diagnostic
+    /// as soon as it is raised.
+    pub fn with_emitter<E>(emitter: E) -> Self
+    where
+        E: Emitter<D> + Send + Sync + 'static,
+    {
+        let mut this = Self::new();
+        this.set_emitter(emitter);
+        this
+    }
+
+    /// Sets the emitter of diagnostics raised from now on. Diagnostics already
+    /// in the collection are emitted when it is next
+    /// [flushed](Diagnostics::flush).
+    pub fn set_emitter<E>(&mut self, emitter: E) -> &mut Self
+    where
+        E: Emitter<D> + Send + Sync + 'static,
+    {
+        self.emitter = Some(Box::new(emitter));
+        self
+    }
+
+    /// Removes the emitter of this collection, returning it, if any.
+    pub fn take_emitter(
+        &mut self,
+    ) -> Option<Box<dyn Emitter<D> + Send + Sync>> {
+        self.emitter.take()
+    }
+
+    /// Returns whether diagnostics are emitted as soon as they are raised,
+    /// which is the default.
+    pub fn is_streaming(&self) -> bool {
+        self.streaming
+    }
+
+    /// Sets whether diagnostics are emitted as soon as they are raised, or
+    /// only when the collection is [flushed](Diagnostics::flush).
+    pub fn set_streaming(&mut self, streaming: bool) -> &mut Self {
+        self.streaming = streaming;
+        self
+    }
+
+    /// Emits the diagnostics not emitted yet, in their current order, followed
+    /// by a summary of the errors omitted since the last flush, if any, and
+    /// then flushes the emitter. Does nothing without an emitter. Returns the
+    /// first error the emitter failed with since the last flush, if any.
+    ///
+    /// This is meant to be called once the compilation is over, after
+    /// suppressions, deduplication and sorting.
+    pub fn flush(&mut self) -> io::Result<()> {
+        if let Some(emitter) = &mut self.emitter {
+            let mut results = Vec::new();
+            for entry in &mut self.elements {
+                if !entry.emitted {
+                    entry.emitted = true;
+                    results.push(emitter.emit(entry.level, &entry.diagnostic));
+                }
+            }
+            let omitted = self.omitted - self.reported_omitted;
+            if omitted > 0 {
+                self.reported_omitted = self.omitted;
+                results.push(emitter.emit_omitted(omitted));
+            }
+            results.push(emitter.flush());
+            for result in results {
+                if let Err(error) = result {
+                    self.emit_error.get_or_insert(error);
+                }
+            }
+        }
+        match self.emit_error.take() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Policy applied to raised diagnostics.
+    pub fn policy(&self) -> &Policy {
+        &self.policy
+    }
+
+    /// Mutable access to the policy applied to raised diagnostics. Changes
+    /// only affect diagnostics raised afterwards.
+    pub fn policy_mut(&mut self) -> &mut Policy {
+        &mut self.policy
+    }
+
+    /// Maximum number of hard errors stored, if any.
+    pub fn error_limit(&self) -> Option<usize> {
+        self.error_limit
+    }
+
+    /// Sets the maximum number of hard errors stored. Once it is reached,
+    /// further hard errors are only counted as omitted. `None` means no limit.
+    pub fn set_error_limit(&mut self, limit: Option<usize>) -> &mut Self {
+        self.error_limit = limit;
+        self
+    }
+
+    /// Returns whether the error limit has been reached, i.e. further hard
+    /// errors will be omitted. Lexers and parsers may use this to stop early.
+    pub fn is_exhausted(&self) -> bool {
+        self.error_limit.is_some_and(|limit| self.errors >= limit)
+    }
+
+    /// Number of hard errors raised, both stored and omitted.
+    pub fn error_count(&self) -> usize {
+        self.errors + self.omitted
+    }
+
+    /// Number of hard errors raised after the error limit was reached, and thus
+    /// not stored.
+    pub fn omitted_errors(&self) -> usize {
+        self.omitted
     }
 
     /// Returns whether the source code status is OK (no hard errors) according
@@ -198,25 +558,312 @@ where
     /// Returns whether the source code status is NOT OK (there are hard errors)
     /// according to this collection of diagnostics.
     pub fn is_err(&self) -> bool {
-        self.max_level().map_or(false, |level| level >= Level::Error)
+        self.max_level().is_some_and(|level| level >= Level::Error)
     }
 
-    /// Returns the maximum level among the diagnostics in this collection.
+    /// Returns the maximum effective level among the diagnostics in this
+    /// collection, including omitted errors.
     pub fn max_level(&self) -> Option<Level> {
-        self.iter().map(Diagnostic::level).max()
+        let max = self.iter_leveled().map(|(level, _)| level).max();
+        if self.omitted > 0 {
+            max.max(Some(Level::Error))
+        } else {
+            max
+        }
     }
 
     /// Creates an iterator over references of diagnostics.
-    pub fn iter(&self) -> Iter<D> {
+    pub fn iter(&self) -> Iter<'_, D> {
         self.into_iter()
     }
 
-    /// Raises a new diagnostic and saves it in this collection.
+    /// Creates an iterator over references of diagnostics together with
+    /// their effective levels.
+    pub fn iter_leveled(&self) -> LeveledIter<'_, D> {
+        LeveledIter { inner: self.elements.iter() }
+    }
+
+    /// Creates an iterator over references of diagnostics whose primary span
+    /// is in the given source.
+    pub fn iter_source<'this>(
+        &'this self,
+        source: &'this Source,
+    ) -> SourceIter<'this, D> {
+        SourceIter { inner: self.iter(), source }
+    }
+
+    /// Creates an iterator over references of diagnostics of type `T`, e.g.
+    /// every `MismatchedToken`.
+    pub fn of_type<T>(&self) -> OfType<'_, D, T>
+    where
+        T: Diagnostic,
+    {
+        OfType { inner: self.iter(), _marker: PhantomData }
+    }
+
+    /// Number of diagnostics stored with the given effective level. Omitted
+    /// errors are not counted, see
+    /// [`omitted_errors`](Diagnostics::omitted_errors).
+    pub fn count(&self, level: Level) -> usize {
+        self.iter_leveled().filter(|&(other, _)| other == level).count()
+    }
+
+    /// Retains only the diagnostics for which the given predicate returns
+    /// `true`, dropping the others.
+    pub fn retain<F>(&mut self, mut predicate: F)
+    where
+        F: FnMut(&D) -> bool,
+    {
+        self.retain_leveled(|_, diagnostic| predicate(diagnostic));
+    }
+
+    /// Retains only the diagnostics for which the given predicate returns
+    /// `true`, like [`retain`](Diagnostics::retain), but also passing their
+    /// effective levels to the predicate.
+    pub fn retain_leveled<F>(&mut self, mut predicate: F)
+    where
+        F: FnMut(Level, &D) -> bool,
+    {
+        self.elements.retain(|entry| predicate(entry.level, &entry.diagnostic));
+        self.errors = self
+            .elements
+            .iter()
+            .filter(|entry| entry.level >= Level::Error)
+            .count();
+    }
+
+    /// Removes every diagnostic from this collection, returning them in an
+    /// iterator. The count of omitted errors is reset as well, but the policy,
+    /// the error limit and the emitter are kept.
+    pub fn drain(&mut self) -> Drain<'_, D> {
+        self.errors = 0;
+        self.omitted = 0;
+        self.reported_omitted = 0;
+        Drain { inner: self.elements.drain(..) }
+    }
+
+    /// Moves the diagnostics of another collection to the end of this one.
+    /// The moved diagnostics keep the levels given by the other collection's
+    /// policy, but are subject to this collection's error limit. Errors the
+    /// other collection omitted are counted as omitted here too. Diagnostics
+    /// the other collection already emitted are not emitted again.
+    pub fn merge(&mut self, other: Self) {
+        self.merge_entries(other.omitted, other.elements);
+    }
+
+    /// Moves the diagnostics of another collection to the end of this one,
+    /// like [`merge`](Diagnostics::merge), but leaving the other collection
+    /// empty instead of consuming it.
+    pub fn append(&mut self, other: &mut Self) {
+        other.errors = 0;
+        other.reported_omitted = 0;
+        let omitted = mem::take(&mut other.omitted);
+        self.merge_entries(omitted, mem::take(&mut other.elements));
+    }
+
+    /// Moves the given entries to the end of this collection, subject to
+    /// the error limit, adding the given count of omitted errors. Entries
+    /// already emitted by their former collection are not emitted again.
+    fn merge_entries(&mut self, omitted: usize, elements: Vec<Entry<D>>) {
+        self.omitted += omitted;
+        for mut entry in elements {
+            if entry.level >= Level::Error {
+                if entry.level < Level::Bug && self.is_exhausted() {
+                    self.omitted += 1;
+                    continue;
+                }
+                self.errors += 1;
+            }
+            if !entry.emitted {
+                entry.emitted = self.emit(entry.level, &entry.diagnostic);
+            }
+            self.elements.push(entry);
+        }
+    }
+
+    /// Removes duplicate diagnostics, keeping the first occurrence. Two
+    /// diagnostics are duplicates if they have the same code (or the same type,
+    /// if they have no code), the same message and the same primary span.
+    pub fn dedup(&mut self) {
+        let mut seen = HashSet::new();
+        self.retain(|diagnostic| {
+            let identity = match diagnostic.code() {
+                Some(code) => Identity::Code(code.to_owned()),
+                None => Identity::Type(Any::type_id(diagnostic)),
+            };
+            let message = diagnostic.to_string();
+            seen.insert((identity, message, diagnostic.primary_span()))
+        });
+    }
+
+    /// Sorts the diagnostics by the location of their primary spans: first by
+    /// source name, then by start and end positions. Distinct sources with the
+    /// same name keep the order of their first appearance in the collection.
+    /// Diagnostics without a primary span come last. The sort is stable, i.e.
+    /// diagnostics at the same place (or without spans) keep their relative
+    /// order.
+    pub fn sort(&mut self) {
+        let mut appearances = HashMap::new();
+        for entry in &self.elements {
+            if let Some(span) = entry.diagnostic.primary_span() {
+                let count = appearances.len();
+                appearances.entry(span.source().clone()).or_insert(count);
+            }
+        }
+        self.elements.sort_by_cached_key(|entry| {
+            let span = entry.diagnostic.primary_span();
+            let key = span.map(|span| {
+                let source = span.source();
+                (
+                    source.name().to_owned(),
+                    appearances[source],
+                    span.start().position(),
+                    span.end().position(),
+                )
+            });
+            (key.is_none(), key)
+        });
+    }
+
+    /// Splits this collection by the source of the primary spans, in order of
+    /// first appearance. Diagnostics without a primary span, and omitted
+    /// errors, are grouped under `None`. Every group keeps the policy and the
+    /// error limit of this collection, but not its emitter.
+    pub fn split_by_source(mut self) -> Vec<(Option<Source>, Self)> {
+        let elements = mem::take(&mut self.elements);
+        let mut groups: Vec<(Option<Source>, Self)> = Vec::new();
+        let mut indices = HashMap::new();
+        let mut index_of = |groups: &mut Vec<_>, source: Option<Source>| {
+            *indices.entry(source.clone()).or_insert_with(|| {
+                groups.push((source, self.empty_like()));
+                groups.len() - 1
+            })
+        };
+        for entry in elements {
+            let span = entry.diagnostic.primary_span();
+            let index =
+                index_of(&mut groups, span.map(|span| span.source().clone()));
+            let group = &mut groups[index].1;
+            if entry.level >= Level::Error {
+                group.errors += 1;
+            }
+            group.elements.push(entry);
+        }
+        if self.omitted > 0 {
+            let index = index_of(&mut groups, None);
+            groups[index].1.omitted = self.omitted;
+        }
+        groups
+    }
+
+    /// Applies every machine-applicable suggestion of the diagnostics in this
+    /// collection to the given rewriter. Suggestions touching other sources or
+    /// conflicting with edits already in the rewriter are skipped. Returns how
+    /// many suggestions were applied.
+    pub fn apply_suggestions(&self, rewriter: &mut Rewriter) -> usize {
+        let mut applied = 0;
+        for diagnostic in self {
+            for suggestion in diagnostic.suggestions() {
+                if suggestion.applicability.is_machine_applicable()
+                    && suggestion.apply(rewriter).is_ok()
+                {
+                    applied += 1;
+                }
+            }
+        }
+        applied
+    }
+
+    /// Raises a new diagnostic and saves it in this collection with the
+    /// level given by the policy, unless the policy drops it, or unless it is
+    /// a hard error (other than a [bug](Level::Bug)) and the error limit has
+    /// been reached.
     pub fn raise<T>(&mut self, diagnostic: T)
     where
         Box<T>: Into<Box<D>>,
     {
-        self.elements.push(Box::new(diagnostic).into());
+        let diagnostic = Box::new(diagnostic).into();
+        if let Some(level) = self.policy.resolve(&*diagnostic) {
+            if level >= Level::Error {
+                if level < Level::Bug && self.is_exhausted() {
+                    self.omitted += 1;
+                    return;
+                }
+                self.errors += 1;
+            }
+            let emitted = self.emit(level, &diagnostic);
+            self.elements.push(Entry { level, diagnostic, emitted });
+        }
+    }
+
+    /// Runs the given compilation, converting a panic into an
+    /// [`InternalError`] raised into this collection. The error reports the
+    /// innermost span entered (see [`enter_span`]) when the panic happened.
+    /// Returns the result of the compilation, or `None` if it panicked.
+    ///
+    /// The panic is still reported by the panic hook. Since the compilation
+    /// is interrupted, whatever state it shares should not be trusted after a
+    /// panic, except for this collection.
+    pub fn catch_ice<F, T>(&mut self, compile: F) -> Option<T>
+    where
+        F: FnOnce(&mut Self) -> T,
+        Box<InternalError>: Into<Box<D>>,
+    {
+        ice::clear_panicked_span();
+        match panic::catch_unwind(AssertUnwindSafe(|| compile(self))) {
+            Ok(value) => Some(value),
+            Err(payload) => {
+                self.raise(InternalError::from_panic(payload));
+                None
+            },
+        }
+    }
+
+    /// Creates an empty collection with the same policy and error limit as
+    /// this one, but without emitter.
+    fn empty_like(&self) -> Self {
+        let mut empty = Self::with_policy(self.policy.clone());
+        empty.error_limit = self.error_limit;
+        empty
+    }
+
+    /// Emits the given diagnostic if there is an emitter and the collection
+    /// is streaming, saving the error it fails with, if it is the first one.
+    /// Returns whether the diagnostic was emitted.
+    fn emit(&mut self, level: Level, diagnostic: &D) -> bool {
+        match &mut self.emitter {
+            Some(emitter) if self.streaming => {
+                if let Err(error) = emitter.emit(level, diagnostic) {
+                    self.emit_error.get_or_insert(error);
+                }
+                true
+            },
+            _ => false,
+        }
+    }
+}
+
+/// Identity of a diagnostic's kind, used to find duplicates.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Identity {
+    /// Identified by its code.
+    Code(String),
+    /// Identified by its type, since it has no code.
+    Type(TypeId),
+}
+
+impl<D, T> Extend<T> for Diagnostics<D>
+where
+    D: Diagnostic + ?Sized,
+    Box<T>: Into<Box<D>>,
+{
+    fn extend<I>(&mut self, iterable: I)
+    where
+        I: IntoIterator<Item = T>,
+    {
+        for diagnostic in iterable {
+            self.raise(diagnostic);
+        }
     }
 }
 
@@ -227,7 +874,7 @@ where
     D: Diagnostic + ?Sized,
 {
     /// Inner owned vector iterator.
-    inner: vec::IntoIter<Box<D>>,
+    inner: vec::IntoIter<Entry<D>>,
 }
 
 impl<D> Iterator for IntoIter<D>
@@ -237,7 +884,7 @@ where
     type Item = Box<D>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next()
+        self.inner.next().map(|entry| entry.diagnostic)
     }
 }
 
@@ -260,7 +907,7 @@ where
     D: Diagnostic + ?Sized,
 {
     /// Iterator over slice references.
-    inner: slice::Iter<'diag, Box<D>>,
+    inner: slice::Iter<'diag, Entry<D>>,
 }
 
 impl<'diag, D> Iterator for Iter<'diag, D>
@@ -270,7 +917,7 @@ where
     type Item = &'diag D;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next().map(|boxed| boxed.as_ref())
+        self.inner.next().map(|entry| entry.diagnostic.as_ref())
     }
 }
 
@@ -285,3 +932,344 @@ where
         Iter { inner: self.elements.iter() }
     }
 }
+
+/// Borrowed iterator over diagnostics of the [`Diagnostics`] collection and
+/// their effective levels.
+#[derive(Debug)]
+pub struct LeveledIter<'diag, D>
+where
+    D: Diagnostic + ?Sized,
+{
+    /// Iterator over slice references.
+    inner: slice::Iter<'diag, Entry<D>>,
+}
+
+impl<'diag, D> Iterator for LeveledIter<'diag, D>
+where
+    D: Diagnostic + ?Sized,
+{
+    type Item = (Level, &'diag D);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|entry| (entry.level, entry.diagnostic.as_ref()))
+    }
+}
+
+/// Iterator draining the diagnostics of a [`Diagnostics`] collection.
+#[derive(Debug)]
+pub struct Drain<'diag, D>
+where
+    D: Diagnostic + ?Sized,
+{
+    /// Inner draining vector iterator.
+    inner: vec::Drain<'diag, Entry<D>>,
+}
+
+impl<'diag, D> Iterator for Drain<'diag, D>
+where
+    D: Diagnostic + ?Sized,
+{
+    type Item = Box<D>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|entry| entry.diagnostic)
+    }
+}
+
+/// Borrowed iterator over diagnostics of the [`Diagnostics`] collection whose
+/// primary span is in a given source.
+#[derive(Debug)]
+pub struct SourceIter<'diag, D>
+where
+    D: Diagnostic + ?Sized,
+{
+    /// Iterator over every diagnostic.
+    inner: Iter<'diag, D>,
+    /// The source looked for.
+    source: &'diag Source,
+}
+
+impl<'diag, D> Iterator for SourceIter<'diag, D>
+where
+    D: Diagnostic + ?Sized,
+{
+    type Item = &'diag D;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let source = self.source;
+        for diagnostic in &mut self.inner {
+            let span = diagnostic.primary_span();
+            if span.is_some_and(|span| span.source() == source) {
+                return Some(diagnostic);
+            }
+        }
+        None
+    }
+}
+
+/// Borrowed iterator over diagnostics of the [`Diagnostics`] collection of a
+/// given type `T`.
+#[derive(Debug)]
+pub struct OfType<'diag, D, T>
+where
+    D: Diagnostic + ?Sized,
+{
+    /// Iterator over every diagnostic.
+    inner: Iter<'diag, D>,
+    /// The type looked for.
+    _marker: PhantomData<fn() -> &'diag T>,
+}
+
+impl<'diag, D, T> Iterator for OfType<'diag, D, T>
+where
+    D: Diagnostic + ?Sized,
+    T: Diagnostic,
+{
+    type Item = &'diag T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.by_ref().find_map(|diagnostic| {
+            if Any::type_id(diagnostic) == TypeId::of::<T>() {
+                // Same check as in `downcast_ref`, but for any `D`.
+                Some(unsafe { &*(diagnostic as *const D as *const T) })
+            } else {
+                None
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Diagnostic, Diagnostics, Level};
+    use crate::source::{Source, Span};
+    use std::fmt;
+
+    #[derive(Debug)]
+    struct Unused {
+        span: Span,
+    }
+
+    impl fmt::Display for Unused {
+        fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmtr, "unused variable `{}`", self.span.as_str())
+        }
+    }
+
+    impl Diagnostic for Unused {
+        fn level(&self) -> Level {
+            Level::Warning
+        }
+
+        fn primary_span(&self) -> Option<Span> {
+            Some(self.span.clone())
+        }
+    }
+
+    #[derive(Debug)]
+    struct Expected {
+        what: &'static str,
+        span: Option<Span>,
+    }
+
+    impl fmt::Display for Expected {
+        fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmtr, "expected {}", self.what)
+        }
+    }
+
+    impl Diagnostic for Expected {
+        fn level(&self) -> Level {
+            Level::Error
+        }
+
+        fn primary_span(&self) -> Option<Span> {
+            self.span.clone()
+        }
+    }
+
+    fn messages(diagnostics: &Diagnostics) -> Vec<String> {
+        diagnostics.iter().map(|diagnostic| diagnostic.to_string()).collect()
+    }
+
+    #[test]
+    fn dedup() {
+        let source = Source::new("main.lam", "\\x x");
+        let mut diagnostics: Diagnostics = Diagnostics::new();
+        let raised = [("`.`", 2), ("`.`", 2), ("`)`", 2), ("`.`", 4)];
+        for &(what, start) in &raised {
+            let span = Some(source.span(start, 1));
+            diagnostics.raise(Expected { what, span });
+        }
+        diagnostics.raise(Expected { what: "`.`", span: None });
+        diagnostics.raise(Expected { what: "`.`", span: None });
+        diagnostics.dedup();
+        assert_eq!(
+            messages(&diagnostics),
+            ["expected `.`", "expected `)`", "expected `.`", "expected `.`"]
+        );
+    }
+
+    #[test]
+    fn sort() {
+        let first = Source::new("a.lam", "x y z");
+        let second = Source::new("b.lam", "x y z");
+        let mut diagnostics: Diagnostics = Diagnostics::new();
+        diagnostics.raise(Expected { what: "nothing", span: None });
+        diagnostics
+            .raise(Expected { what: "b2", span: Some(second.span(2, 1)) });
+        diagnostics
+            .raise(Expected { what: "a4", span: Some(first.span(4, 1)) });
+        diagnostics.raise(Expected { what: "anything", span: None });
+        diagnostics
+            .raise(Expected { what: "a0", span: Some(first.span(0, 1)) });
+        diagnostics
+            .raise(Expected { what: "b0", span: Some(second.span(0, 1)) });
+        diagnostics.sort();
+        assert_eq!(
+            messages(&diagnostics),
+            [
+                "expected a0",
+                "expected a4",
+                "expected b0",
+                "expected b2",
+                "expected nothing",
+                "expected anything",
+            ]
+        );
+    }
+
+    #[test]
+    fn sort_same_name() {
+        let first = Source::new("main.lam", "x y");
+        let second = Source::new("main.lam", "x y");
+        let mut diagnostics: Diagnostics = Diagnostics::new();
+        for &(what, source, start) in &[
+            ("second2", &second, 2),
+            ("first2", &first, 2),
+            ("second0", &second, 0),
+            ("first0", &first, 0),
+        ] {
+            let span = Some(source.span(start, 1));
+            diagnostics.raise(Expected { what, span });
+        }
+        diagnostics.sort();
+        assert_eq!(
+            messages(&diagnostics),
+            [
+                "expected second0",
+                "expected second2",
+                "expected first0",
+                "expected first2",
+            ]
+        );
+    }
+
+    #[test]
+    fn count_and_downcast() {
+        let source = Source::new("main.lam", "\\x y");
+        let mut diagnostics: Diagnostics = Diagnostics::new();
+        diagnostics.raise(Unused { span: source.span(1, 1) });
+        diagnostics.raise(Expected { what: "`.`", span: None });
+        diagnostics.raise(Unused { span: source.span(3, 1) });
+        assert_eq!(diagnostics.count(Level::Warning), 2);
+        assert_eq!(diagnostics.count(Level::Error), 1);
+        assert_eq!(diagnostics.count(Level::Note), 0);
+
+        let unused = diagnostics
+            .of_type::<Unused>()
+            .map(|unused| unused.span.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(unused, ["x", "y"]);
+        let first = diagnostics.iter().next().unwrap();
+        assert!(first.is::<Unused>());
+        assert!(!first.is::<Expected>());
+        assert!(first.downcast_ref::<Unused>().is_some());
+        assert!(first.downcast_ref::<Expected>().is_none());
+    }
+
+    #[test]
+    fn drain_append_extend() {
+        let source = Source::new("main.lam", "\\x y");
+        let mut diagnostics: Diagnostics = Diagnostics::new();
+        diagnostics.set_error_limit(Some(1));
+        diagnostics.extend(vec![
+            Expected { what: "`.`", span: None },
+            Expected { what: "`)`", span: None },
+        ]);
+        assert_eq!(diagnostics.error_count(), 2);
+        assert_eq!(diagnostics.omitted_errors(), 1);
+
+        let mut other: Diagnostics = Diagnostics::new();
+        other.raise(Unused { span: source.span(1, 1) });
+        other.raise(Expected { what: "`(`", span: None });
+        diagnostics.append(&mut other);
+        assert!(other.is_ok());
+        assert_eq!(other.iter().count(), 0);
+        assert_eq!(
+            messages(&diagnostics),
+            ["expected `.`", "unused variable `x`"]
+        );
+        assert_eq!(diagnostics.omitted_errors(), 2);
+
+        let drained = diagnostics
+            .drain()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(drained, ["expected `.`", "unused variable `x`"]);
+        assert!(diagnostics.is_ok());
+        assert_eq!(diagnostics.max_level(), None);
+        assert_eq!(diagnostics.error_limit(), Some(1));
+    }
+
+    #[test]
+    fn split_by_source() {
+        let first = Source::new("a.lam", "x y");
+        let second = Source::new("b.lam", "x y");
+        let mut diagnostics: Diagnostics = Diagnostics::new();
+        diagnostics.set_error_limit(Some(2));
+        diagnostics.raise(Unused { span: second.span(0, 1) });
+        diagnostics
+            .raise(Expected { what: "a2", span: Some(first.span(2, 1)) });
+        diagnostics.raise(Expected { what: "nothing", span: None });
+        diagnostics.raise(Unused { span: first.span(0, 1) });
+        diagnostics
+            .raise(Expected { what: "b2", span: Some(second.span(2, 1)) });
+
+        let in_first = diagnostics
+            .iter_source(&first)
+            .map(|diagnostic| diagnostic.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(in_first, ["expected a2", "unused variable `x`"]);
+
+        let groups = diagnostics.split_by_source();
+        let groups = groups
+            .iter()
+            .map(|(source, group)| {
+                let name = source.as_ref().map(|source| source.name());
+                (
+                    name,
+                    messages(group),
+                    group.error_count(),
+                    group.omitted_errors(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            groups,
+            [
+                (Some("b.lam"), vec!["unused variable `x`".to_owned()], 0, 0),
+                (
+                    Some("a.lam"),
+                    vec![
+                        "expected a2".to_owned(),
+                        "unused variable `x`".to_owned()
+                    ],
+                    1,
+                    0
+                ),
+                (None, vec!["expected nothing".to_owned()], 2, 1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,403 @@
+//! This module provides localizable messages of diagnostics and catalogs of
+//! their translations.
+//!
+//! Only the main message of a diagnostic is localizable, and only the
+//! human-readable renderers ([`Renderer`](super::Renderer), and through it
+//! [`HumanEmitter`](super::HumanEmitter), and
+//! [`HtmlRenderer`](super::HtmlRenderer)) translate it. Label messages,
+//! children and machine-readable outputs are left as given.
+
+use super::Diagnostic;
+use std::{collections::BTreeMap, error::Error, fmt};
+
+/// A localizable message: an identifier to be looked up in a [`Catalog`],
+/// and named arguments to be interpolated into the translation.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Message {
+    /// Identifier of the message, e.g. `unmatched-open-paren`.
+    pub id: String,
+    /// Arguments of the message, by name, already formatted.
+    pub args: Vec<(String, String)>,
+}
+
+impl Message {
+    /// Creates a message with the given identifier and no arguments.
+    pub fn new<S>(id: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self { id: id.into(), args: Vec::new() }
+    }
+
+    /// Adds an argument with the given name, formatting its value.
+    pub fn with_arg<S, T>(mut self, name: S, value: T) -> Self
+    where
+        S: Into<String>,
+        T: fmt::Display,
+    {
+        self.args.push((name.into(), value.to_string()));
+        self
+    }
+
+    /// Finds the value of the argument with the given name, if any.
+    pub fn arg(&self, name: &str) -> Option<&str> {
+        self.args
+            .iter()
+            .find(|(arg, _)| arg == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// A piece of a translation.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Part {
+    /// Literal text.
+    Text(String),
+    /// Placeable of an argument, `{ $name }`.
+    Arg(String),
+}
+
+/// A catalog of translations of [`Message`]s, grouped by locale (e.g. `en`,
+/// `pt-BR`).
+///
+/// Translations are loaded from resources in a simple format inspired by
+/// [Fluent](https://projectfluent.org):
+///
+/// ```text
+/// # Comments start with `#`.
+/// unmatched-open-paren = parêntese de abertura `{ $paren }` sem par
+/// long-message = first line
+///     indented lines continue the previous message
+/// braces = literal braces are written as { "{" } and { "}" }
+/// ```
+///
+/// Arguments missing from a message are shown as the placeable itself, e.g.
+/// `{$paren}`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Catalog {
+    /// Translations indexed by locale, then by message identifier.
+    locales: BTreeMap<String, BTreeMap<String, Vec<Part>>>,
+}
+
+impl Catalog {
+    /// Creates an empty catalog.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the translations of the given resource to the given locale. If
+    /// the resource is invalid, or defines a message already defined for the
+    /// locale, nothing is added.
+    pub fn add_resource(
+        &mut self,
+        locale: &str,
+        resource: &str,
+    ) -> Result<&mut Self, ResourceError> {
+        let existing = self.locales.get(locale);
+        let mut parsed = BTreeMap::new();
+        let mut current: Option<(usize, String, String)> = None;
+        for (line, text) in resource.lines().enumerate() {
+            let trimmed = text.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if text.starts_with(char::is_whitespace) {
+                let (_, _, value) = current.as_mut().ok_or(ResourceError {
+                    line,
+                    kind: ResourceErrorKind::DanglingContinuation,
+                })?;
+                if !value.is_empty() {
+                    value.push('\n');
+                }
+                value.push_str(trimmed);
+                continue;
+            }
+            if let Some((line, id, value)) = current.take() {
+                parsed.insert(id, parse_pattern(line, &value)?);
+            }
+            let (id, value) = text.split_once('=').ok_or(ResourceError {
+                line,
+                kind: ResourceErrorKind::MissingEquals,
+            })?;
+            let id = id.trim();
+            if !is_identifier(id) {
+                let kind = ResourceErrorKind::InvalidIdentifier(id.to_owned());
+                return Err(ResourceError { line, kind });
+            }
+            let defined = existing.is_some_and(|map| map.contains_key(id));
+            if defined || parsed.contains_key(id) {
+                let kind = ResourceErrorKind::Duplicate(id.to_owned());
+                return Err(ResourceError { line, kind });
+            }
+            current = Some((line, id.to_owned(), value.trim().to_owned()));
+        }
+        if let Some((line, id, value)) = current {
+            parsed.insert(id, parse_pattern(line, &value)?);
+        }
+        self.locales.entry(locale.to_owned()).or_default().extend(parsed);
+        Ok(self)
+    }
+
+    /// Returns whether the given locale has a translation of the given
+    /// message identifier.
+    pub fn contains(&self, locale: &str, id: &str) -> bool {
+        self.locales.get(locale).is_some_and(|map| map.contains_key(id))
+    }
+
+    /// Translates the given message into the given locale. If the locale has
+    /// no translation, less specific locales are tried, e.g. `pt` for
+    /// `pt-BR`. Returns `None` if no translation was found.
+    pub fn format(&self, locale: &str, message: &Message) -> Option<String> {
+        let mut locale = locale;
+        let parts = loop {
+            let found =
+                self.locales.get(locale).and_then(|map| map.get(&message.id));
+            if let Some(parts) = found {
+                break parts;
+            }
+            locale = &locale[.. locale.rfind(['-', '_'])?];
+        };
+        let mut output = String::new();
+        for part in parts {
+            match part {
+                Part::Text(text) => output.push_str(text),
+                Part::Arg(name) => match message.arg(name) {
+                    Some(value) => output.push_str(value),
+                    None => {
+                        output.push_str("{$");
+                        output.push_str(name);
+                        output.push('}');
+                    },
+                },
+            }
+        }
+        Some(output)
+    }
+
+    /// Translates the message of the given diagnostic into the given locale
+    /// (see [`Catalog::format`]), falling back to its `Display` text if it
+    /// has no localizable message or no translation was found.
+    pub fn localize<D>(&self, locale: &str, diagnostic: &D) -> String
+    where
+        D: Diagnostic + ?Sized,
+    {
+        diagnostic
+            .message()
+            .and_then(|message| self.format(locale, &message))
+            .unwrap_or_else(|| diagnostic.to_string())
+    }
+}
+
+/// Returns whether the given text is a valid message identifier: an ASCII
+/// letter followed by ASCII letters, digits, `-` or `_`.
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars.next().is_some_and(|ch| ch.is_ascii_alphabetic())
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+}
+
+/// Parses the value of a message defined at the given line.
+fn parse_pattern(line: usize, value: &str) -> Result<Vec<Part>, ResourceError> {
+    let error = |kind| ResourceError { line, kind };
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut rest = value;
+    while let Some(index) = rest.find(['{', '}']) {
+        text.push_str(&rest[.. index]);
+        if rest[index ..].starts_with('}') {
+            return Err(error(ResourceErrorKind::UnopenedPlaceable));
+        }
+        let body = rest[index + 1 ..].trim_start();
+        // Literals are scanned first, since they may contain braces.
+        let (placeable, after) = match body.strip_prefix('"') {
+            Some(literal) => {
+                let end = literal.find('"').ok_or_else(|| {
+                    error(ResourceErrorKind::UnclosedPlaceable)
+                })?;
+                (&body[.. end + 2], literal[end + 1 ..].trim_start())
+            },
+            None => {
+                let end = body.find('}').ok_or_else(|| {
+                    error(ResourceErrorKind::UnclosedPlaceable)
+                })?;
+                (body[.. end].trim_end(), &body[end ..])
+            },
+        };
+        rest = after
+            .strip_prefix('}')
+            .ok_or_else(|| error(ResourceErrorKind::UnclosedPlaceable))?;
+        if let Some(name) = placeable.strip_prefix('$') {
+            if !is_identifier(name) {
+                let kind =
+                    ResourceErrorKind::InvalidPlaceable(placeable.into());
+                return Err(error(kind));
+            }
+            if !text.is_empty() {
+                parts.push(Part::Text(std::mem::take(&mut text)));
+            }
+            parts.push(Part::Arg(name.to_owned()));
+        } else if placeable.starts_with('"') {
+            text.push_str(&placeable[1 .. placeable.len() - 1]);
+        } else {
+            let kind = ResourceErrorKind::InvalidPlaceable(placeable.into());
+            return Err(error(kind));
+        }
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        parts.push(Part::Text(text));
+    }
+    Ok(parts)
+}
+
+/// Kind of error found in a resource of translations.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ResourceErrorKind {
+    /// A line is neither a comment, a definition, nor a continuation.
+    MissingEquals,
+    /// An indented line does not follow a definition.
+    DanglingContinuation,
+    /// A message identifier is invalid.
+    InvalidIdentifier(String),
+    /// A message is defined more than once.
+    Duplicate(String),
+    /// A placeable is neither `{ $name }` nor `{ "literal" }`.
+    InvalidPlaceable(String),
+    /// A `{` is never closed.
+    UnclosedPlaceable,
+    /// A `}` was never opened.
+    UnopenedPlaceable,
+}
+
+impl fmt::Display for ResourceErrorKind {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResourceErrorKind::MissingEquals => {
+                write!(fmtr, "expected `identifier = message`")
+            },
+            ResourceErrorKind::DanglingContinuation => {
+                write!(fmtr, "indented line does not continue a message")
+            },
+            ResourceErrorKind::InvalidIdentifier(id) => {
+                write!(fmtr, "invalid message identifier `{}`", id)
+            },
+            ResourceErrorKind::Duplicate(id) => {
+                write!(fmtr, "message `{}` is already defined", id)
+            },
+            ResourceErrorKind::InvalidPlaceable(placeable) => {
+                write!(fmtr, "invalid placeable `{{{}}}`", placeable)
+            },
+            ResourceErrorKind::UnclosedPlaceable => {
+                write!(fmtr, "unclosed `{{`")
+            },
+            ResourceErrorKind::UnopenedPlaceable => {
+                write!(fmtr, "unopened `}}`, write `{{ \"}}\" }}` instead")
+            },
+        }
+    }
+}
+
+/// Error returned when a resource of translations is invalid.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ResourceError {
+    /// Line of the error, starting from `0`.
+    pub line: usize,
+    /// Kind of the error.
+    pub kind: ResourceErrorKind,
+}
+
+impl fmt::Display for ResourceError {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmtr, "line {}: {}", self.line + 1, self.kind)
+    }
+}
+
+impl Error for ResourceError {}
+
+#[cfg(test)]
+mod test {
+    use super::{Catalog, Message, ResourceError, ResourceErrorKind};
+    use crate::{
+        diagnostic::{Diagnostic, Level},
+        source::Span,
+    };
+    use std::fmt;
+
+    const PT: &str = "# Mensagens em português.
+unmatched = parêntese `{ $paren }` sem par
+multi = primeira
+    segunda { \"{\" }{ $missing }{ \"}\" }
+";
+
+    #[derive(Debug)]
+    struct Unmatched;
+
+    impl fmt::Display for Unmatched {
+        fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmtr, "unmatched `(`")
+        }
+    }
+
+    impl Diagnostic for Unmatched {
+        fn level(&self) -> Level {
+            Level::Error
+        }
+
+        fn primary_span(&self) -> Option<Span> {
+            None
+        }
+
+        fn message(&self) -> Option<Message> {
+            Some(Message::new("unmatched").with_arg("paren", '('))
+        }
+    }
+
+    #[test]
+    fn format() {
+        let mut catalog = Catalog::new();
+        catalog.add_resource("pt", PT).unwrap();
+        let message = Message::new("unmatched").with_arg("paren", ")");
+        assert_eq!(
+            catalog.format("pt-BR", &message).as_deref(),
+            Some("parêntese `)` sem par")
+        );
+        assert_eq!(catalog.format("en", &message), None);
+        assert_eq!(
+            catalog.format("pt", &Message::new("multi")).as_deref(),
+            Some("primeira\nsegunda {{$missing}}")
+        );
+        assert!(catalog.contains("pt", "multi"));
+        assert!(!catalog.contains("pt-BR", "multi"));
+    }
+
+    #[test]
+    fn localize() {
+        let mut catalog = Catalog::new();
+        catalog.add_resource("pt", PT).unwrap();
+        assert_eq!(catalog.localize("pt", &Unmatched), "parêntese `(` sem par");
+        assert_eq!(catalog.localize("de", &Unmatched), "unmatched `(`");
+    }
+
+    #[test]
+    fn errors() {
+        let cases = [
+            ("a = b\n  c\nd", ResourceErrorKind::MissingEquals, 2),
+            ("  a = b", ResourceErrorKind::DanglingContinuation, 0),
+            ("1a = b", ResourceErrorKind::InvalidIdentifier("1a".into()), 0),
+            ("a = b\na = c", ResourceErrorKind::Duplicate("a".into()), 1),
+            ("a = { x }", ResourceErrorKind::InvalidPlaceable("x".into()), 0),
+            ("a = {$b", ResourceErrorKind::UnclosedPlaceable, 0),
+            ("a = b }", ResourceErrorKind::UnopenedPlaceable, 0),
+        ];
+        for (resource, kind, line) in cases {
+            let mut catalog = Catalog::new();
+            let error = catalog.add_resource("en", resource).unwrap_err();
+            assert_eq!(error, ResourceError { line, kind });
+            assert_eq!(catalog, Catalog::new());
+        }
+        let mut catalog = Catalog::new();
+        catalog.add_resource("en", "a = b").unwrap();
+        assert!(catalog.add_resource("en", "a = c").is_err());
+        assert!(catalog.add_resource("pt", "a = c").is_ok());
+    }
+}
@@ -0,0 +1,58 @@
+//! This module provides child entries of a diagnostic, such as notes and help
+//! messages shown beneath it.
+
+use crate::source::Span;
+use std::fmt;
+
+/// Kind of a [`Child`] entry of a diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ChildKind {
+    /// Additional information, e.g. "first defined here".
+    Note,
+    /// A hint on how to fix the problem, e.g. "consider adding `.`".
+    Help,
+}
+
+impl fmt::Display for ChildKind {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        fmtr.write_str(match self {
+            ChildKind::Note => "note",
+            ChildKind::Help => "help",
+        })
+    }
+}
+
+/// A child entry of a diagnostic, rendered beneath its parent.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Child {
+    /// Whether this is a note or a help message.
+    pub kind: ChildKind,
+    /// The message of this entry.
+    pub message: String,
+    /// The span this entry refers to, if any.
+    pub span: Option<Span>,
+}
+
+impl Child {
+    /// Creates a note with the given message and no span.
+    pub fn note<S>(message: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self { kind: ChildKind::Note, message: message.into(), span: None }
+    }
+
+    /// Creates a help message with the given message and no span.
+    pub fn help<S>(message: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self { kind: ChildKind::Help, message: message.into(), span: None }
+    }
+
+    /// Sets the span this entry refers to.
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+}
@@ -0,0 +1,387 @@
+//! This module provides emitters, which output diagnostics as they are raised
+//! instead of after the whole compilation.
+
+use super::{Diagnostic, Diagnostics, Level, Renderer};
+use crate::source::Span;
+use std::io;
+
+/// Outputs diagnostics one at a time. A [`Diagnostics`] collection created
+/// with an emitter (see [`Diagnostics::with_emitter`]) emits each diagnostic
+/// as soon as it is raised.
+pub trait Emitter<D = dyn Diagnostic + Send + Sync>
+where
+    D: Diagnostic + ?Sized,
+{
+    /// Emits a single diagnostic with the given effective level.
+    fn emit(&mut self, level: Level, diagnostic: &D) -> io::Result<()>;
+
+    /// Emits a summary of the given number of hard errors omitted because of
+    /// an error limit. By default, does nothing.
+    fn emit_omitted(&mut self, count: usize) -> io::Result<()> {
+        let _ = count;
+        Ok(())
+    }
+
+    /// Flushes any buffered output. By default, does nothing.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Emits every diagnostic of the given collection with its effective
+    /// level, and the summary of its omitted errors, if any, then flushes.
+    fn emit_all(&mut self, diagnostics: &Diagnostics<D>) -> io::Result<()> {
+        for (level, diagnostic) in diagnostics.iter_leveled() {
+            self.emit(level, diagnostic)?;
+        }
+        let omitted = diagnostics.omitted_errors();
+        if omitted > 0 {
+            self.emit_omitted(omitted)?;
+        }
+        self.flush()
+    }
+}
+
+impl<E, D> Emitter<D> for &mut E
+where
+    E: Emitter<D> + ?Sized,
+    D: Diagnostic + ?Sized,
+{
+    fn emit(&mut self, level: Level, diagnostic: &D) -> io::Result<()> {
+        (**self).emit(level, diagnostic)
+    }
+
+    fn emit_omitted(&mut self, count: usize) -> io::Result<()> {
+        (**self).emit_omitted(count)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (**self).flush()
+    }
+}
+
+impl<E, D> Emitter<D> for Box<E>
+where
+    E: Emitter<D> + ?Sized,
+    D: Diagnostic + ?Sized,
+{
+    fn emit(&mut self, level: Level, diagnostic: &D) -> io::Result<()> {
+        (**self).emit(level, diagnostic)
+    }
+
+    fn emit_omitted(&mut self, count: usize) -> io::Result<()> {
+        (**self).emit_omitted(count)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (**self).flush()
+    }
+}
+
+/// Emits diagnostics in the human-readable format of a [`Renderer`],
+/// separated by blank lines.
+#[derive(Debug, Clone)]
+pub struct HumanEmitter<W>
+where
+    W: io::Write,
+{
+    /// Where diagnostics are written.
+    output: W,
+    /// Renderer of the diagnostics.
+    renderer: Renderer,
+    /// Whether a diagnostic was already emitted.
+    emitted: bool,
+}
+
+impl<W> HumanEmitter<W>
+where
+    W: io::Write,
+{
+    /// Creates an emitter writing into the given output with the given
+    /// renderer.
+    pub fn new(output: W, renderer: Renderer) -> Self {
+        Self { output, renderer, emitted: false }
+    }
+
+    /// The renderer of the diagnostics.
+    pub fn renderer(&self) -> &Renderer {
+        &self.renderer
+    }
+
+    /// Consumes the emitter, returning its output.
+    pub fn into_inner(self) -> W {
+        self.output
+    }
+}
+
+impl<W, D> Emitter<D> for HumanEmitter<W>
+where
+    W: io::Write,
+    D: Diagnostic + ?Sized,
+{
+    fn emit(&mut self, level: Level, diagnostic: &D) -> io::Result<()> {
+        let mut rendered = String::new();
+        if self.emitted {
+            rendered.push('\n');
+        }
+        self.renderer
+            .write_leveled(&mut rendered, level, diagnostic)
+            .expect("writing into a string cannot fail");
+        self.emitted = true;
+        self.output.write_all(rendered.as_bytes())
+    }
+
+    fn emit_omitted(&mut self, count: usize) -> io::Result<()> {
+        let mut rendered = String::new();
+        if self.emitted {
+            rendered.push('\n');
+        }
+        self.renderer
+            .write_omitted(&mut rendered, count)
+            .expect("writing into a string cannot fail");
+        self.emitted = true;
+        self.output.write_all(rendered.as_bytes())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+}
+
+/// Emits diagnostics in the GCC format, `file:line:col: level: message`, one
+/// per line, which problem matchers of editors understand. Lines and columns
+/// are 1-based, with columns counted in unicode scalar values. The code of a
+/// diagnostic, if any, follows the message in brackets. Children are emitted
+/// as `note` or `help` lines of their own.
+#[derive(Debug, Clone)]
+pub struct GccEmitter<W>
+where
+    W: io::Write,
+{
+    /// Where diagnostics are written.
+    output: W,
+}
+
+impl<W> GccEmitter<W>
+where
+    W: io::Write,
+{
+    /// Creates an emitter writing into the given output.
+    pub fn new(output: W) -> Self {
+        Self { output }
+    }
+
+    /// Consumes the emitter, returning its output.
+    pub fn into_inner(self) -> W {
+        self.output
+    }
+
+    /// Writes the `file:line:col: ` prefix of the given span, if any.
+    fn write_location(&mut self, span: Option<&Span>) -> io::Result<()> {
+        match span {
+            Some(span) => {
+                let start = span.start();
+                write!(
+                    self.output,
+                    "{}:{}:{}: ",
+                    span.source().name(),
+                    start.line() + 1,
+                    start.char_column() + 1
+                )
+            },
+            None => Ok(()),
+        }
+    }
+}
+
+impl<W, D> Emitter<D> for GccEmitter<W>
+where
+    W: io::Write,
+    D: Diagnostic + ?Sized,
+{
+    fn emit(&mut self, level: Level, diagnostic: &D) -> io::Result<()> {
+        let primary = diagnostic.primary_span();
+        self.write_location(primary.as_ref())?;
+        write!(self.output, "{}: {}", level, diagnostic)?;
+        if let Some(code) = diagnostic.code() {
+            write!(self.output, " [{}]", code)?;
+        }
+        writeln!(self.output)?;
+        for child in diagnostic.children() {
+            self.write_location(child.span.as_ref().or(primary.as_ref()))?;
+            writeln!(self.output, "{}: {}", child.kind, child.message)?;
+        }
+        Ok(())
+    }
+
+    fn emit_omitted(&mut self, count: usize) -> io::Result<()> {
+        let plural = if count == 1 { "" } else { "s" };
+        writeln!(
+            self.output,
+            "{}: {} more error{} omitted",
+            Level::Error,
+            count,
+            plural
+        )
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Emitter, GccEmitter, HumanEmitter};
+    use crate::{
+        diagnostic::{Child, Diagnostic, Diagnostics, Level, Renderer},
+        source::{Source, Span},
+    };
+    use std::{
+        fmt,
+        io,
+        sync::{Arc, Mutex},
+    };
+
+    #[derive(Debug, Clone, Default)]
+    struct Shared {
+        buffer: Arc<Mutex<Vec<u8>>>,
+    }
+
+    impl io::Write for Shared {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.buffer.lock().unwrap().write(bytes)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[derive(Debug)]
+    struct Unexpected {
+        span: Option<Span>,
+    }
+
+    impl fmt::Display for Unexpected {
+        fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmtr, "unexpected token")
+        }
+    }
+
+    impl Diagnostic for Unexpected {
+        fn level(&self) -> Level {
+            Level::Error
+        }
+
+        fn primary_span(&self) -> Option<Span> {
+            self.span.clone()
+        }
+
+        fn code(&self) -> Option<&str> {
+            Some("E0002")
+        }
+
+        fn children<'this>(
+            &'this self,
+        ) -> Box<dyn Iterator<Item = Child> + Send + Sync + 'this> {
+            Box::new(Some(Child::help("remove it")).into_iter())
+        }
+    }
+
+    fn unexpected(source: &Source, start: usize) -> Unexpected {
+        let mut reader = source.reader();
+        reader.advance(start);
+        reader.mark();
+        reader.next();
+        Unexpected { span: Some(reader.span()) }
+    }
+
+    #[test]
+    fn gcc() {
+        let source = Source::new("src/main.lam", "ç\n  )");
+        let mut diagnostics: Diagnostics = Diagnostics::new();
+        diagnostics.raise(unexpected(&source, 4));
+        diagnostics.raise(Unexpected { span: None });
+        let mut emitter = GccEmitter::new(Vec::new());
+        emitter.emit_all(&diagnostics).unwrap();
+        let output = String::from_utf8(emitter.into_inner()).unwrap();
+        assert_eq!(
+            output,
+            "src/main.lam:2:3: error: unexpected token [E0002]\n\
+             src/main.lam:2:3: help: remove it\n\
+             error: unexpected token [E0002]\n\
+             help: remove it\n"
+        );
+    }
+
+    #[test]
+    fn streaming() {
+        let source = Source::new("main.lam", "))");
+        let output = Shared::default();
+        let emitter = HumanEmitter::new(output.clone(), Renderer::new());
+        let mut diagnostics: Diagnostics = Diagnostics::with_emitter(emitter);
+        diagnostics.set_error_limit(Some(1));
+        diagnostics.raise(unexpected(&source, 0));
+        let emitted = output.buffer.lock().unwrap().len();
+        assert!(emitted > 0);
+        diagnostics.raise(unexpected(&source, 1));
+        assert_eq!(output.buffer.lock().unwrap().len(), emitted);
+        diagnostics.flush().unwrap();
+        diagnostics.flush().unwrap();
+        let output = String::from_utf8(output.buffer.lock().unwrap().clone());
+        assert_eq!(
+            output.unwrap(),
+            "error[E0002]: unexpected token\n --> main.lam:1:1\n  |\n1 | ))\n  \
+             | ^\n  = help: remove it\n\nerror: 1 more error omitted\n"
+        );
+    }
+
+    #[test]
+    fn deferred() {
+        let source = Source::new("main.lam", "ab");
+        let output = Shared::default();
+        let emitter = GccEmitter::new(output.clone());
+        let mut diagnostics: Diagnostics = Diagnostics::with_emitter(emitter);
+        diagnostics.set_streaming(false);
+        diagnostics.raise(unexpected(&source, 1));
+        diagnostics.raise(unexpected(&source, 0));
+        diagnostics.raise(Unexpected { span: None });
+        assert!(output.buffer.lock().unwrap().is_empty());
+        diagnostics.retain(|diagnostic| diagnostic.primary_span().is_some());
+        diagnostics.sort();
+        diagnostics.flush().unwrap();
+        diagnostics.flush().unwrap();
+        let output = String::from_utf8(output.buffer.lock().unwrap().clone());
+        assert_eq!(
+            output.unwrap(),
+            "main.lam:1:1: error: unexpected token [E0002]\n\
+             main.lam:1:1: help: remove it\n\
+             main.lam:1:2: error: unexpected token [E0002]\n\
+             main.lam:1:2: help: remove it\n"
+        );
+    }
+
+    #[test]
+    fn merge_emitted() {
+        let source = Source::new("main.lam", "ab");
+        let output = Shared::default();
+        let mut diagnostics: Diagnostics =
+            Diagnostics::with_emitter(GccEmitter::new(output.clone()));
+        let mut streamed: Diagnostics =
+            Diagnostics::with_emitter(GccEmitter::new(output.clone()));
+        streamed.raise(unexpected(&source, 0));
+        let mut deferred: Diagnostics = Diagnostics::new();
+        deferred.raise(unexpected(&source, 1));
+        diagnostics.merge(streamed);
+        diagnostics.append(&mut deferred);
+        diagnostics.flush().unwrap();
+        let output = String::from_utf8(output.buffer.lock().unwrap().clone());
+        assert_eq!(
+            output.unwrap(),
+            "main.lam:1:1: error: unexpected token [E0002]\n\
+             main.lam:1:1: help: remove it\n\
+             main.lam:1:2: error: unexpected token [E0002]\n\
+             main.lam:1:2: help: remove it\n"
+        );
+    }
+}
@@ -0,0 +1,74 @@
+//! This module provides conversions of diagnostic fields into spans and
+//! messages, used by code generated by `#[derive(Diagnostic)]`.
+
+use crate::source::Span;
+
+/// A field holding zero or more spans, such as `Span`, `Option<Span>` or
+/// `Vec<Span>`. Fields marked with `#[primary]`, `#[secondary]`, or `#[note =
+/// "..."]`/`#[help = "..."]` in `#[derive(Diagnostic)]` must implement it.
+pub trait SpanField {
+    /// Spans held by this field, in order.
+    fn spans(&self) -> Vec<Span>;
+}
+
+impl SpanField for Span {
+    fn spans(&self) -> Vec<Span> {
+        vec![self.clone()]
+    }
+}
+
+impl<T> SpanField for Option<T>
+where
+    T: SpanField,
+{
+    fn spans(&self) -> Vec<Span> {
+        self.as_ref().map_or_else(Vec::new, SpanField::spans)
+    }
+}
+
+impl<T> SpanField for Vec<T>
+where
+    T: SpanField,
+{
+    fn spans(&self) -> Vec<Span> {
+        self.iter().flat_map(SpanField::spans).collect()
+    }
+}
+
+impl<T> SpanField for Box<T>
+where
+    T: SpanField + ?Sized,
+{
+    fn spans(&self) -> Vec<Span> {
+        (**self).spans()
+    }
+}
+
+/// A field holding an optional message, such as `String` or
+/// `Option<String>`. Fields marked with a bare `#[note]` or `#[help]` in
+/// `#[derive(Diagnostic)]` must implement it.
+pub trait MessageField {
+    /// Message held by this field, if any.
+    fn message(&self) -> Option<String>;
+}
+
+impl MessageField for String {
+    fn message(&self) -> Option<String> {
+        Some(self.clone())
+    }
+}
+
+impl MessageField for &str {
+    fn message(&self) -> Option<String> {
+        Some((*self).to_owned())
+    }
+}
+
+impl<T> MessageField for Option<T>
+where
+    T: MessageField,
+{
+    fn message(&self) -> Option<String> {
+        self.as_ref().and_then(MessageField::message)
+    }
+}
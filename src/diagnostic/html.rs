@@ -0,0 +1,683 @@
+//! This module provides a renderer of a source code and its diagnostics into
+//! a standalone HTML page, e.g. for web playgrounds and CI reports.
+
+use super::{Catalog, ChildKind, Diagnostic, Diagnostics, Level};
+use crate::source::{Source, Span};
+use std::{fmt, sync::Arc};
+
+/// Style sheet embedded in every page.
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; }
+.diagnostics { padding-left: 1.5em; }
+.diagnostics > li { margin-bottom: 0.5em; }
+.diagnostics > li:target { background: #ffffe0; }
+.level { font-weight: bold; }
+.help > .header .level, .highlight.help { color: #0086b3; }
+.note > .header .level, .highlight.note { color: #2a7ab0; }
+.warning > .header .level, .highlight.warning { color: #b08800; }
+.error > .header .level, .highlight.error { color: #cb2431; }
+.bug > .header .level, .highlight.bug { color: #a626a4; }
+.header { color: inherit; text-decoration: none; }
+.children { list-style: none; padding-left: 1em; }
+.kind { font-weight: bold; }
+.source { border-collapse: collapse; font-family: monospace; }
+.source tr:target { background: #ffffe0; }
+.line-number { padding-right: 1em; text-align: right; user-select: none; }
+.line-number a { color: #999; text-decoration: none; }
+.code { white-space: pre; }
+.highlight { text-decoration: none; border-bottom: 2px solid; }
+.highlight.secondary { border-bottom-style: dashed; }
+.highlight.empty { border-bottom: none; border-left: 2px solid; }
+";
+
+/// Renders a source code together with its diagnostics into a standalone
+/// HTML page: a list of the diagnostics, each with its own anchor
+/// (`#diagnostic-0`, `#diagnostic-1`, ...), followed by a listing of the
+/// source with line numbers, also anchored (`#line-1`, `#line-2`, ...).
+///
+/// Spans in the listing are highlighted by level, linking to their
+/// diagnostic, and showing its message when hovered. Source contents and
+/// messages are escaped, so the page is safe to publish.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HtmlRenderer {
+    /// Title of the page, if not the name of the source.
+    title: Option<String>,
+    /// Width of a tab stop, in columns.
+    tab_width: usize,
+    /// Catalog translating messages, if any.
+    catalog: Option<Arc<Catalog>>,
+    /// Locale messages are translated into.
+    locale: String,
+}
+
+impl Default for HtmlRenderer {
+    fn default() -> Self {
+        Self { title: None, tab_width: 4, catalog: None, locale: String::new() }
+    }
+}
+
+impl HtmlRenderer {
+    /// Creates a new renderer with default settings: the source name as
+    /// title, tab stops every 4 columns and no translation of messages.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Title of the page, if not the name of the source.
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// Sets the title of the page. With `None`, the name of the source is
+    /// used.
+    pub fn set_title(&mut self, title: Option<String>) -> &mut Self {
+        self.title = title;
+        self
+    }
+
+    /// Width of a tab stop, in columns.
+    pub fn tab_width(&self) -> usize {
+        self.tab_width
+    }
+
+    /// Sets the width of a tab stop, in columns.
+    ///
+    /// # Panics
+    /// Panics if the width is zero.
+    pub fn set_tab_width(&mut self, tab_width: usize) -> &mut Self {
+        if tab_width == 0 {
+            panic!("Tab width cannot be zero");
+        }
+        self.tab_width = tab_width;
+        self
+    }
+
+    /// Catalog translating messages, if any.
+    pub fn catalog(&self) -> Option<&Catalog> {
+        self.catalog.as_deref()
+    }
+
+    /// Sets the catalog translating messages of diagnostics with a
+    /// [localizable message](Diagnostic::message). Without a catalog, or
+    /// without a translation, the `Display` text of diagnostics is shown.
+    /// Only the main message is translated; label messages and children are
+    /// shown as given.
+    pub fn set_catalog(&mut self, catalog: Option<Arc<Catalog>>) -> &mut Self {
+        self.catalog = catalog;
+        self
+    }
+
+    /// Locale messages are translated into.
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Sets the locale messages are translated into, e.g. `pt-BR`.
+    pub fn set_locale<S>(&mut self, locale: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.locale = locale.into();
+        self
+    }
+
+    /// Creates a type that, when displayed, renders the page of the given
+    /// source and diagnostics.
+    pub fn render<'this, 'diag, D>(
+        &'this self,
+        source: &'diag Source,
+        diagnostics: &'diag Diagnostics<D>,
+    ) -> HtmlPage<'this, 'diag, D>
+    where
+        D: Diagnostic + ?Sized,
+    {
+        HtmlPage { renderer: self, source, diagnostics }
+    }
+
+    /// Writes the page of the given source and diagnostics into the given
+    /// output, with the levels given by the collection's policy. Spans of
+    /// other sources are not highlighted, but their diagnostics are still
+    /// listed.
+    pub fn write<W, D>(
+        &self,
+        output: &mut W,
+        source: &Source,
+        diagnostics: &Diagnostics<D>,
+    ) -> fmt::Result
+    where
+        W: fmt::Write + ?Sized,
+        D: Diagnostic + ?Sized,
+    {
+        let title = self.title.as_deref().unwrap_or_else(|| source.name());
+        output.write_str("<!DOCTYPE html>\n<html>\n<head>\n")?;
+        output.write_str("<meta charset=\"utf-8\">\n<title>")?;
+        write_escaped(output, title)?;
+        output.write_str("</title>\n<style>\n")?;
+        output.write_str(STYLE)?;
+        writeln!(output, ".code {{ tab-size: {}; }}", self.tab_width)?;
+        output.write_str("</style>\n</head>\n<body>\n<h1>")?;
+        write_escaped(output, title)?;
+        output.write_str("</h1>\n")?;
+
+        let mut highlights = Vec::new();
+        output.write_str("<ol class=\"diagnostics\">\n")?;
+        for (index, (level, diagnostic)) in
+            diagnostics.iter_leveled().enumerate()
+        {
+            let header = match diagnostic.code() {
+                Some(code) => format!("{}[{}]", level, code),
+                None => level.to_string(),
+            };
+            let message = match &self.catalog {
+                Some(catalog) => catalog.localize(&self.locale, diagnostic),
+                None => diagnostic.to_string(),
+            };
+            let item =
+                Item { index, level, header: &header, message: &message };
+            self.write_item(output, source, &item, diagnostic)?;
+            for label in diagnostic.labels() {
+                if label.span.source() != source {
+                    continue;
+                }
+                let mut tooltip = format!("{}: {}", header, message);
+                if let Some(label_message) = &label.message {
+                    tooltip.push('\n');
+                    tooltip.push_str(label_message);
+                }
+                highlights.push(Highlight {
+                    start: label.span.start().position(),
+                    end: label.span.end().position(),
+                    level,
+                    primary: label.is_primary(),
+                    index,
+                    tooltip,
+                });
+            }
+        }
+        output.write_str("</ol>\n")?;
+        let omitted = diagnostics.omitted_errors();
+        if omitted > 0 {
+            let plural = if omitted == 1 { "" } else { "s" };
+            writeln!(
+                output,
+                "<p class=\"omitted\">{} more error{} omitted</p>",
+                omitted, plural
+            )?;
+        }
+
+        self.write_listing(output, source, &highlights)?;
+        output.write_str("</body>\n</html>\n")
+    }
+
+    /// Writes the list item of a diagnostic: its header, linking to its own
+    /// anchor, its location and its children.
+    fn write_item<W, D>(
+        &self,
+        output: &mut W,
+        source: &Source,
+        item: &Item,
+        diagnostic: &D,
+    ) -> fmt::Result
+    where
+        W: fmt::Write + ?Sized,
+        D: Diagnostic + ?Sized,
+    {
+        write!(
+            output,
+            "<li id=\"diagnostic-{}\" class=\"{}\">\n<a class=\"header\" \
+             href=\"#diagnostic-{}\"><span class=\"level\">",
+            item.index,
+            level_class(item.level),
+            item.index
+        )?;
+        write_escaped(output, item.header)?;
+        output.write_str("</span>: ")?;
+        write_escaped(output, item.message)?;
+        output.write_str("</a>\n")?;
+        if let Some(span) = diagnostic.primary_span() {
+            write_location(output, source, &span)?;
+            output.write_str("\n")?;
+        }
+        let mut children = diagnostic.children().peekable();
+        if children.peek().is_some() {
+            output.write_str("<ul class=\"children\">\n")?;
+            for child in children {
+                let kind = match child.kind {
+                    ChildKind::Note => "note",
+                    ChildKind::Help => "help",
+                };
+                write!(
+                    output,
+                    "<li class=\"{}\"><span class=\"kind\">{}</span>: ",
+                    kind, child.kind
+                )?;
+                write_escaped(output, &child.message)?;
+                if let Some(span) = child.span {
+                    output.write_str(" ")?;
+                    write_location(output, source, &span)?;
+                }
+                output.write_str("</li>\n")?;
+            }
+            output.write_str("</ul>\n")?;
+        }
+        output.write_str("</li>\n")
+    }
+
+    /// Writes the listing of the source, one table row per line, with the
+    /// given highlights.
+    fn write_listing<W>(
+        &self,
+        output: &mut W,
+        source: &Source,
+        highlights: &[Highlight],
+    ) -> fmt::Result
+    where
+        W: fmt::Write + ?Sized,
+    {
+        let mut last_line = source.line(source.len());
+        let trailing_newline = source.contents().ends_with('\n');
+        if trailing_newline
+            && highlights.iter().all(|highlight| highlight.end < source.len())
+        {
+            last_line -= 1;
+        }
+
+        output.write_str("<table class=\"source\">\n")?;
+        for line in 0 ..= last_line {
+            let number = line + 1;
+            write!(
+                output,
+                "<tr id=\"line-{}\"><td class=\"line-number\"><a \
+                 href=\"#line-{}\">{}</a></td><td class=\"code\">",
+                number, number, number
+            )?;
+            let start = source.line_start(line);
+            let end = source.line_end(line);
+            let mut bounds = vec![start, end];
+            for highlight in highlights {
+                for &bound in &[highlight.start, highlight.end] {
+                    if bound > start && bound < end {
+                        bounds.push(bound);
+                    }
+                }
+            }
+            bounds.sort_unstable();
+            bounds.dedup();
+
+            for window in bounds.windows(2) {
+                self.write_empty(output, highlights, window[0])?;
+                self.write_run(
+                    output, source, highlights, window[0], window[1],
+                )?;
+            }
+            self.write_empty(output, highlights, end)?;
+            output.write_str("</td></tr>\n")?;
+        }
+        output.write_str("</table>\n")
+    }
+
+    /// Writes the run of source contents in the given range of positions,
+    /// highlighted by the highlights covering it, if any.
+    fn write_run<W>(
+        &self,
+        output: &mut W,
+        source: &Source,
+        highlights: &[Highlight],
+        start: usize,
+        end: usize,
+    ) -> fmt::Result
+    where
+        W: fmt::Write + ?Sized,
+    {
+        // Bounds splitting a character are moved back to its start, so the
+        // run ending inside it is empty and the next one includes it.
+        let from = source.floor_char_offset(start);
+        let to = source.floor_char_offset(end);
+        let text = &source.contents()[from .. to];
+        let covering = highlights
+            .iter()
+            .filter(|highlight| {
+                highlight.start <= start && end <= highlight.end
+            })
+            .collect::<Vec<_>>();
+        if covering.is_empty() {
+            write_escaped(output, text)
+        } else {
+            Highlight::write_tag(output, &covering, false)?;
+            write_escaped(output, text)?;
+            output.write_str("</a>")
+        }
+    }
+
+    /// Writes a marker for the empty highlights at the given position, if
+    /// any.
+    fn write_empty<W>(
+        &self,
+        output: &mut W,
+        highlights: &[Highlight],
+        position: usize,
+    ) -> fmt::Result
+    where
+        W: fmt::Write + ?Sized,
+    {
+        let empty = highlights
+            .iter()
+            .filter(|highlight| {
+                highlight.start == position && highlight.end == position
+            })
+            .collect::<Vec<_>>();
+        if empty.is_empty() {
+            Ok(())
+        } else {
+            Highlight::write_tag(output, &empty, true)?;
+            output.write_str("</a>")
+        }
+    }
+}
+
+/// Type that, when displayed, renders the page of a source and its
+/// diagnostics as HTML.
+#[derive(Debug)]
+pub struct HtmlPage<'renderer, 'diag, D>
+where
+    D: Diagnostic + ?Sized,
+{
+    /// The renderer being used.
+    renderer: &'renderer HtmlRenderer,
+    /// The source being listed.
+    source: &'diag Source,
+    /// The diagnostics being rendered.
+    diagnostics: &'diag Diagnostics<D>,
+}
+
+impl<'renderer, 'diag, D> fmt::Display for HtmlPage<'renderer, 'diag, D>
+where
+    D: Diagnostic + ?Sized,
+{
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        self.renderer.write(fmtr, self.source, self.diagnostics)
+    }
+}
+
+/// Header of a diagnostic in the list.
+#[derive(Debug, Clone, Copy)]
+struct Item<'text> {
+    /// Index of the diagnostic in the list.
+    index: usize,
+    /// Effective level of the diagnostic.
+    level: Level,
+    /// Level and code of the diagnostic.
+    header: &'text str,
+    /// Message of the diagnostic, possibly translated.
+    message: &'text str,
+}
+
+/// A span of the listed source to be highlighted, in positions.
+#[derive(Debug, Clone)]
+struct Highlight {
+    /// Start position of the span.
+    start: usize,
+    /// End position of the span.
+    end: usize,
+    /// Effective level of the diagnostic.
+    level: Level,
+    /// Whether the span comes from a primary label.
+    primary: bool,
+    /// Index of the diagnostic in the list.
+    index: usize,
+    /// Text shown when hovering the span.
+    tooltip: String,
+}
+
+impl Highlight {
+    /// Writes the opening tag of the link highlighting a range covered by
+    /// the given highlights. The strongest highlight gives the style and the
+    /// target of the link, while every tooltip is shown.
+    fn write_tag<W>(
+        output: &mut W,
+        covering: &[&Highlight],
+        empty: bool,
+    ) -> fmt::Result
+    where
+        W: fmt::Write + ?Sized,
+    {
+        let strongest = covering
+            .iter()
+            .max_by_key(|highlight| {
+                (highlight.primary, highlight.level, !highlight.index)
+            })
+            .expect("at least one highlight");
+        write!(
+            output,
+            "<a class=\"highlight {}",
+            level_class(strongest.level)
+        )?;
+        if !strongest.primary {
+            output.write_str(" secondary")?;
+        }
+        if empty {
+            output.write_str(" empty")?;
+        }
+        write!(output, "\" href=\"#diagnostic-{}\" title=\"", strongest.index)?;
+        for (i, highlight) in covering.iter().enumerate() {
+            if i > 0 {
+                output.write_str("\n")?;
+            }
+            write_escaped(output, &highlight.tooltip)?;
+        }
+        output.write_str("\">")
+    }
+}
+
+/// Class of the elements of the given level in the page.
+fn level_class(level: Level) -> &'static str {
+    match level {
+        Level::Help => "help",
+        Level::Note => "note",
+        Level::Warning => "warning",
+        Level::Error => "error",
+        Level::Bug => "bug",
+    }
+}
+
+/// Writes the location of the given span, linking to its line if the span
+/// is in the listed source.
+fn write_location<W>(
+    output: &mut W,
+    source: &Source,
+    span: &Span,
+) -> fmt::Result
+where
+    W: fmt::Write + ?Sized,
+{
+    let (line, column) = span.start().line_column();
+    let listed = span.source() == source;
+    if listed {
+        write!(output, "<a class=\"location\" href=\"#line-{}\">", line + 1)?;
+    } else {
+        output.write_str("<span class=\"location\">")?;
+    }
+    write_escaped(output, span.source().name())?;
+    write!(output, ":{}:{}", line + 1, column + 1)?;
+    output.write_str(if listed { "</a>" } else { "</span>" })
+}
+
+/// Writes the given text escaped as HTML, valid both as element contents
+/// and as attribute values.
+fn write_escaped<W>(output: &mut W, text: &str) -> fmt::Result
+where
+    W: fmt::Write + ?Sized,
+{
+    let mut rest = text;
+    while let Some(index) = rest.find(['&', '<', '>', '"', '\'']) {
+        output.write_str(&rest[.. index])?;
+        output.write_str(match rest.as_bytes()[index] {
+            b'&' => "&amp;",
+            b'<' => "&lt;",
+            b'>' => "&gt;",
+            b'"' => "&quot;",
+            _ => "&#39;",
+        })?;
+        rest = &rest[index + 1 ..];
+    }
+    output.write_str(rest)
+}
+
+#[cfg(test)]
+mod test {
+    use super::HtmlRenderer;
+    use crate::{
+        diagnostic::{Child, Diagnostic, Diagnostics, Label, Level},
+        source::{Segmentation, Source, Span},
+    };
+    use std::fmt;
+
+    #[derive(Debug)]
+    struct Unmatched {
+        open: Span,
+        close: Span,
+    }
+
+    impl fmt::Display for Unmatched {
+        fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmtr, "unmatched `{}`", self.open.as_str())
+        }
+    }
+
+    impl Diagnostic for Unmatched {
+        fn level(&self) -> Level {
+            Level::Error
+        }
+
+        fn primary_span(&self) -> Option<Span> {
+            Some(self.open.clone())
+        }
+
+        fn code(&self) -> Option<&str> {
+            Some("E0003")
+        }
+
+        fn labels<'this>(
+            &'this self,
+        ) -> Box<dyn Iterator<Item = Label> + Send + Sync + 'this> {
+            let open =
+                Label::primary(self.open.clone()).with_message("opened <here>");
+            let close = Label::secondary(self.close.clone());
+            Box::new(vec![open, close].into_iter())
+        }
+
+        fn children<'this>(
+            &'this self,
+        ) -> Box<dyn Iterator<Item = Child> + Send + Sync + 'this> {
+            Box::new(Some(Child::help("add `>`")).into_iter())
+        }
+    }
+
+    #[derive(Debug)]
+    struct Unused {
+        span: Span,
+    }
+
+    impl fmt::Display for Unused {
+        fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmtr, "unused \"{}\"", self.span.as_str())
+        }
+    }
+
+    impl Diagnostic for Unused {
+        fn level(&self) -> Level {
+            Level::Warning
+        }
+
+        fn primary_span(&self) -> Option<Span> {
+            Some(self.span.clone())
+        }
+    }
+
+    #[test]
+    fn page() {
+        let source = Source::new("a<b>.lam", "x = <y\n&z\n");
+        let mut diagnostics: Diagnostics = Diagnostics::new();
+        diagnostics.raise(Unmatched {
+            open: source.span(4, 1),
+            close: source.span(9, 0),
+        });
+        diagnostics.raise(Unused { span: source.span(4, 2) });
+        let page =
+            HtmlRenderer::new().render(&source, &diagnostics).to_string();
+
+        assert!(page.starts_with("<!DOCTYPE html>\n"));
+        assert!(page.contains("<title>a&lt;b&gt;.lam</title>"));
+        assert!(page.contains(
+            "<li id=\"diagnostic-0\" class=\"error\">\n<a class=\"header\" \
+             href=\"#diagnostic-0\"><span class=\"level\">error[E0003]</span>: \
+             unmatched `&lt;`</a>\n<a class=\"location\" \
+             href=\"#line-1\">a&lt;b&gt;.lam:1:5</a>\n<ul class=\"children\">\n\
+             <li class=\"help\"><span class=\"kind\">help</span>: add \
+             `&gt;`</li>\n</ul>\n</li>\n"
+        ));
+        assert!(page.contains(
+            "<tr id=\"line-1\"><td class=\"line-number\"><a \
+             href=\"#line-1\">1</a></td><td class=\"code\">x = <a \
+             class=\"highlight error\" href=\"#diagnostic-0\" \
+             title=\"error[E0003]: unmatched `&lt;`\nopened &lt;here&gt;\n\
+             warning: unused &quot;&lt;y&quot;\">&lt;</a><a \
+             class=\"highlight warning\" href=\"#diagnostic-1\" \
+             title=\"warning: unused &quot;&lt;y&quot;\">y</a></td></tr>\n"
+        ));
+        assert!(page.contains(
+            "<td class=\"code\">&amp;z<a class=\"highlight error secondary \
+             empty\" href=\"#diagnostic-0\" title=\"error[E0003]: unmatched \
+             `&lt;`\"></a></td></tr>\n</table>\n"
+        ));
+        assert!(!page.contains("id=\"line-3\""));
+    }
+
+    #[test]
+    fn omitted_and_foreign() {
+        let source = Source::new("main.lam", "x");
+        let other = Source::new("other.lam", "y");
+        let mut diagnostics: Diagnostics = Diagnostics::new();
+        diagnostics.set_error_limit(Some(1));
+        diagnostics.raise(Unmatched {
+            open: other.span(0, 1),
+            close: other.span(1, 0),
+        });
+        diagnostics.raise(Unused { span: source.span(0, 1) });
+        let mut renderer = HtmlRenderer::new();
+        renderer.set_title(Some("Report".to_owned())).set_tab_width(2);
+        let page = renderer.render(&source, &diagnostics).to_string();
+
+        assert!(page.contains("<title>Report</title>"));
+        assert!(page.contains(".code { tab-size: 2; }"));
+        assert!(page.contains("<span class=\"location\">other.lam:1:1</span>"));
+        assert!(page.contains(
+            "<td class=\"code\"><a class=\"highlight warning\" \
+             href=\"#diagnostic-1\" title=\"warning: unused \
+             &quot;x&quot;\">x</a></td>"
+        ));
+        assert!(!page.contains("<p class=\"omitted\">"));
+        diagnostics.raise(Unused { span: source.span(0, 1) });
+        diagnostics.raise(Unmatched {
+            open: source.span(0, 1),
+            close: source.span(1, 0),
+        });
+        let page = renderer.render(&source, &diagnostics).to_string();
+        assert!(page.contains("<p class=\"omitted\">1 more error omitted</p>"));
+    }
+
+    #[test]
+    fn split_chars() {
+        let source =
+            Source::with_segmentation("a.bin", "é!", Segmentation::Bytes);
+        let mut diagnostics: Diagnostics = Diagnostics::new();
+        diagnostics.raise(Unused { span: source.span(1, 2) });
+        let page =
+            HtmlRenderer::new().render(&source, &diagnostics).to_string();
+        assert!(page.contains(
+            "<td class=\"code\"><a class=\"highlight warning\" \
+             href=\"#diagnostic-0\" title=\"warning: unused \
+             &quot;é!&quot;\">é!</a></td>"
+        ));
+    }
+}
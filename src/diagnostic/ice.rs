@@ -0,0 +1,207 @@
+//! This module provides the conversion of panics into internal compiler
+//! errors, reporting the span being processed when the panic happened.
+
+use super::{Child, Diagnostic, Level};
+use crate::source::Span;
+use std::{any::Any, cell::RefCell, fmt, marker::PhantomData, thread};
+
+thread_local! {
+    /// Spans entered by the current thread, innermost last.
+    static SPANS: RefCell<Vec<Span>> = const { RefCell::new(Vec::new()) };
+    /// Innermost span entered when the current thread started panicking.
+    static PANICKED_AT: RefCell<Option<Span>> = const { RefCell::new(None) };
+}
+
+/// Marks the given span as being processed by the current thread, until the
+/// returned guard is dropped. Guards can be nested, e.g. one per item being
+/// type-checked and one per expression.
+///
+/// If the thread panics while the guard is alive, the innermost span entered
+/// is reported by [`Diagnostics::catch_ice`](super::Diagnostics::catch_ice).
+/// A panic caught inside the compilation is forgotten once a span is entered
+/// or left again.
+pub fn enter_span(span: Span) -> SpanGuard {
+    clear_panicked_span();
+    let depth = SPANS.with(|spans| {
+        let mut spans = spans.borrow_mut();
+        spans.push(span);
+        spans.len() - 1
+    });
+    SpanGuard { depth, _thread: PhantomData }
+}
+
+/// Innermost span being processed by the current thread (see
+/// [`enter_span`]), if any.
+pub fn current_span() -> Option<Span> {
+    SPANS.with(|spans| spans.borrow().last().cloned())
+}
+
+/// Takes the span recorded when the current thread last panicked, falling
+/// back to the current span.
+pub(super) fn take_panicked_span() -> Option<Span> {
+    PANICKED_AT.with(|span| span.borrow_mut().take()).or_else(current_span)
+}
+
+/// Forgets the span recorded when the current thread last panicked, if any.
+pub(super) fn clear_panicked_span() {
+    PANICKED_AT.with(|span| span.borrow_mut().take());
+}
+
+/// Guard returned by [`enter_span`], leaving the span when dropped.
+#[derive(Debug)]
+#[must_use = "the span is left as soon as the guard is dropped"]
+pub struct SpanGuard {
+    /// Length of the stack of spans before the span was entered.
+    depth: usize,
+    /// Spans are entered per thread, so the guard cannot be sent.
+    _thread: PhantomData<*const ()>,
+}
+
+impl Drop for SpanGuard {
+    fn drop(&mut self) {
+        SPANS.with(|spans| {
+            let mut spans = spans.borrow_mut();
+            if thread::panicking() {
+                // Innermost guards are dropped first while unwinding.
+                PANICKED_AT.with(|span| {
+                    let mut span = span.borrow_mut();
+                    if span.is_none() {
+                        *span = spans.last().cloned();
+                    }
+                });
+            } else {
+                // Not unwinding, so any panic recorded was caught.
+                clear_panicked_span();
+            }
+            spans.truncate(self.depth);
+        });
+    }
+}
+
+/// An internal compiler error, raised by
+/// [`Diagnostics::catch_ice`](super::Diagnostics::catch_ice) when the
+/// compilation panics. Its level is [`Level::Bug`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InternalError {
+    /// Message of the panic.
+    pub message: String,
+    /// Span being processed when the panic happened, if known.
+    pub span: Option<Span>,
+}
+
+impl InternalError {
+    /// Creates an internal error from the payload of a panic, with the span
+    /// recorded when the current thread panicked.
+    pub(super) fn from_panic(payload: Box<dyn Any + Send>) -> Self {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => match payload.downcast::<&'static str>() {
+                Ok(message) => (*message).to_owned(),
+                Err(_) => "the compiler panicked".to_owned(),
+            },
+        };
+        Self { message, span: take_panicked_span() }
+    }
+}
+
+impl fmt::Display for InternalError {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        fmtr.write_str(&self.message)
+    }
+}
+
+impl Diagnostic for InternalError {
+    fn level(&self) -> Level {
+        Level::Bug
+    }
+
+    fn primary_span(&self) -> Option<Span> {
+        self.span.clone()
+    }
+
+    fn children<'this>(
+        &'this self,
+    ) -> Box<dyn Iterator<Item = Child> + Send + Sync + 'this> {
+        let note =
+            Child::note("this is a bug in the compiler, please report it");
+        Box::new(Some(note).into_iter())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{current_span, enter_span};
+    use crate::{
+        diagnostic::{Diagnostics, Level, Renderer},
+        source::Source,
+    };
+    use std::panic;
+
+    #[test]
+    fn guards() {
+        let source = Source::new("main.lam", "f (x y)");
+        assert_eq!(current_span(), None);
+        let outer = enter_span(source.span(0, 7));
+        {
+            let _inner = enter_span(source.span(2, 5));
+            assert_eq!(current_span(), Some(source.span(2, 5)));
+        }
+        assert_eq!(current_span(), Some(source.span(0, 7)));
+        drop(outer);
+        assert_eq!(current_span(), None);
+    }
+
+    #[test]
+    fn catch_ice() {
+        let source = Source::new("main.lam", "f (x y)");
+        let mut diagnostics: Diagnostics = Diagnostics::new();
+        diagnostics.set_error_limit(Some(0));
+        let result = diagnostics.catch_ice(|_| {
+            let _item = enter_span(source.span(0, 7));
+            let _expr = enter_span(source.span(3, 1));
+            panic!("unbound variable {}", "x");
+        });
+        assert_eq!(result, None::<()>);
+        assert_eq!(current_span(), None);
+        assert!(diagnostics.is_err());
+        assert_eq!(diagnostics.max_level(), Some(Level::Bug));
+        let ice = diagnostics.iter().next().unwrap();
+        assert_eq!(ice.to_string(), "unbound variable x");
+        assert_eq!(ice.primary_span(), Some(source.span(3, 1)));
+
+        assert_eq!(diagnostics.catch_ice(|_| 42), Some(42));
+        let _outer = enter_span(source.span(0, 1));
+        diagnostics.catch_ice(|_| panic!("oops"));
+        let ice = diagnostics.iter().nth(1).unwrap();
+        assert_eq!(ice.to_string(), "oops");
+        assert_eq!(ice.primary_span(), Some(source.span(0, 1)));
+        let rendered = Renderer::new().render(ice).to_string();
+        assert_eq!(
+            rendered,
+            "internal compiler error: oops\n --> main.lam:1:1\n  |\n1 | f \
+             (x y)\n  | ^\n  = note: this is a bug in the compiler, please \
+             report it\n"
+        );
+    }
+
+    #[test]
+    fn nested_catch() {
+        let source = Source::new("main.lam", "f (x y)");
+        let mut diagnostics: Diagnostics = Diagnostics::new();
+        diagnostics.catch_ice(|_| {
+            let _item = enter_span(source.span(0, 7));
+            let caught = panic::catch_unwind(|| {
+                let _expr = enter_span(source.span(3, 1));
+                panic!("recovered");
+            });
+            assert!(caught.is_err());
+            {
+                let _arg = enter_span(source.span(5, 1));
+            }
+            panic!("oops");
+        });
+        let ice = diagnostics.iter().next().unwrap();
+        assert_eq!(ice.to_string(), "oops");
+        assert_eq!(ice.primary_span(), Some(source.span(0, 7)));
+    }
+}
@@ -0,0 +1,343 @@
+//! This module provides JSON serialization of diagnostics, for consumption by
+//! editors, CI bots and other tools. Only available with the `json` feature.
+//!
+//! Each diagnostic is serialized as an object of the following shape:
+//!
+//! ```text
+//! {
+//!     "level": "error",
+//!     "code": "E0003",
+//!     "message": "unmatched opening parenthesis `(`",
+//!     "source": "main.lam",
+//!     "primary_span": <span>,
+//!     "labels": [{ "primary": true, "message": "...", "span": <span> }],
+//!     "children": [{ "kind": "help", "message": "...", "span": <span> }],
+//!     "suggestions": [{
+//!         "message": "...",
+//!         "applicability": "machine-applicable",
+//!         "edits": [{ "span": <span>, "replacement": "..." }]
+//!     }]
+//! }
+//! ```
+//!
+//! Where a span is an object with the source name, its contents, and start
+//! and end positions:
+//!
+//! ```text
+//! {
+//!     "source": "main.lam",
+//!     "text": "(",
+//!     "start": { "byte": 4, "position": 4, "line": 1, "column": 5 },
+//!     "end": { "byte": 5, "position": 5, "line": 1, "column": 6 }
+//! }
+//! ```
+//!
+//! `position` counts string segments (grapheme clusters), and `line` and
+//! `column` are 1-based, with columns counted in string segments as well.
+//! Absent codes, spans and source names are `null`.
+//!
+//! [`JsonEmitter`] writes the same objects, one per line, as diagnostics are
+//! raised.
+
+use super::{
+    Applicability,
+    Child,
+    Diagnostic,
+    Diagnostics,
+    Emitter,
+    Label,
+    Level,
+    Suggestion,
+};
+use crate::source::{Location, Span};
+use serde_json::{json, Value};
+use std::io;
+
+/// Converts a single diagnostic into a JSON value.
+pub fn to_value<D>(diagnostic: &D) -> Value
+where
+    D: Diagnostic + ?Sized,
+{
+    let primary_span = diagnostic.primary_span();
+    let labels = diagnostic
+        .labels()
+        .map(|label| label_to_value(&label))
+        .collect::<Vec<_>>();
+    let children = diagnostic
+        .children()
+        .map(|child| child_to_value(&child))
+        .collect::<Vec<_>>();
+    let suggestions = diagnostic
+        .suggestions()
+        .map(|suggestion| suggestion_to_value(&suggestion))
+        .collect::<Vec<_>>();
+    json!({
+        "level": diagnostic.level().to_string(),
+        "code": diagnostic.code(),
+        "message": diagnostic.to_string(),
+        "source": primary_span.as_ref().map(|span| span.source().name()),
+        "primary_span": primary_span.as_ref().map(span_to_value),
+        "labels": labels,
+        "children": children,
+        "suggestions": suggestions,
+    })
+}
+
+/// Converts a collection of diagnostics into a JSON array, with the levels
+/// given by the collection's policy.
+pub fn to_array<D>(diagnostics: &Diagnostics<D>) -> Value
+where
+    D: Diagnostic + ?Sized,
+{
+    Value::Array(
+        diagnostics
+            .iter_leveled()
+            .map(|(level, diagnostic)| leveled_to_value(level, diagnostic))
+            .collect(),
+    )
+}
+
+/// Writes the diagnostics as JSON, one object per line, with the levels given
+/// by the collection's policy.
+pub fn write_lines<W, D>(
+    output: &mut W,
+    diagnostics: &Diagnostics<D>,
+) -> io::Result<()>
+where
+    W: io::Write + ?Sized,
+    D: Diagnostic + ?Sized,
+{
+    for (level, diagnostic) in diagnostics.iter_leveled() {
+        let value = leveled_to_value(level, diagnostic);
+        serde_json::to_writer(&mut *output, &value)?;
+        writeln!(output)?;
+    }
+    Ok(())
+}
+
+/// Writes the diagnostics as a single JSON array, with the levels given by
+/// the collection's policy.
+pub fn write_array<W, D>(
+    output: &mut W,
+    diagnostics: &Diagnostics<D>,
+) -> io::Result<()>
+where
+    W: io::Write + ?Sized,
+    D: Diagnostic + ?Sized,
+{
+    serde_json::to_writer(&mut *output, &to_array(diagnostics))?;
+    Ok(())
+}
+
+/// Emits diagnostics as JSON, one object per line, as soon as they are
+/// raised.
+#[derive(Debug, Clone)]
+pub struct JsonEmitter<W>
+where
+    W: io::Write,
+{
+    /// Where diagnostics are written.
+    output: W,
+}
+
+impl<W> JsonEmitter<W>
+where
+    W: io::Write,
+{
+    /// Creates an emitter writing into the given output.
+    pub fn new(output: W) -> Self {
+        Self { output }
+    }
+
+    /// Consumes the emitter, returning its output.
+    pub fn into_inner(self) -> W {
+        self.output
+    }
+}
+
+impl<W, D> Emitter<D> for JsonEmitter<W>
+where
+    W: io::Write,
+    D: Diagnostic + ?Sized,
+{
+    fn emit(&mut self, level: Level, diagnostic: &D) -> io::Result<()> {
+        let value = leveled_to_value(level, diagnostic);
+        serde_json::to_writer(&mut self.output, &value)?;
+        writeln!(self.output)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+}
+
+/// Converts a diagnostic into a JSON value, as if it had the given level.
+fn leveled_to_value<D>(level: Level, diagnostic: &D) -> Value
+where
+    D: Diagnostic + ?Sized,
+{
+    let mut value = to_value(diagnostic);
+    value["level"] = json!(level.to_string());
+    value
+}
+
+/// Converts a span into a JSON value.
+pub fn span_to_value(span: &Span) -> Value {
+    json!({
+        "source": span.source().name(),
+        "text": span.as_str(),
+        "start": location_to_value(&span.start()),
+        "end": location_to_value(&span.end()),
+    })
+}
+
+/// Converts a location into a JSON value.
+pub fn location_to_value(location: &Location) -> Value {
+    let (line, column) = location.line_column();
+    json!({
+        "byte": location.byte_offset(),
+        "position": location.position(),
+        "line": line + 1,
+        "column": column + 1,
+    })
+}
+
+/// Converts a label into a JSON value.
+fn label_to_value(label: &Label) -> Value {
+    json!({
+        "primary": label.is_primary(),
+        "message": label.message,
+        "span": span_to_value(&label.span),
+    })
+}
+
+/// Converts a child entry into a JSON value.
+fn child_to_value(child: &Child) -> Value {
+    json!({
+        "kind": child.kind.to_string(),
+        "message": child.message,
+        "span": child.span.as_ref().map(span_to_value),
+    })
+}
+
+/// Converts a suggestion into a JSON value.
+fn suggestion_to_value(suggestion: &Suggestion) -> Value {
+    let applicability = match suggestion.applicability {
+        Applicability::MachineApplicable => "machine-applicable",
+        Applicability::MaybeIncorrect => "maybe-incorrect",
+        Applicability::HasPlaceholders => "has-placeholders",
+    };
+    let edits = suggestion
+        .edits
+        .iter()
+        .map(|edit| {
+            json!({
+                "span": span_to_value(edit.span()),
+                "replacement": edit.replacement(),
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "message": suggestion.message,
+        "applicability": applicability,
+        "edits": edits,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{to_value, write_lines, JsonEmitter};
+    use crate::{
+        diagnostic::{Diagnostic, Diagnostics, Emitter, Level},
+        source::{Source, Span},
+    };
+    use serde_json::{json, Value};
+    use std::fmt;
+
+    #[derive(Debug)]
+    struct Unexpected {
+        span: Span,
+    }
+
+    impl fmt::Display for Unexpected {
+        fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmtr, "unexpected {}", self.span.as_str())
+        }
+    }
+
+    impl Diagnostic for Unexpected {
+        fn level(&self) -> Level {
+            Level::Warning
+        }
+
+        fn primary_span(&self) -> Option<Span> {
+            Some(self.span.clone())
+        }
+    }
+
+    #[test]
+    fn diagnostic_value() {
+        let source = Source::new("main.lam", "ç\n(ç)");
+        let mut reader = source.reader();
+        reader.advance(3);
+        reader.mark();
+        reader.next();
+        let diagnostic = Unexpected { span: reader.span() };
+        let span = json!({
+            "source": "main.lam",
+            "text": "ç",
+            "start": { "byte": 4, "position": 3, "line": 2, "column": 2 },
+            "end": { "byte": 6, "position": 4, "line": 2, "column": 3 },
+        });
+        assert_eq!(
+            to_value(&diagnostic),
+            json!({
+                "level": "warning",
+                "code": null,
+                "message": "unexpected ç",
+                "source": "main.lam",
+                "primary_span": span,
+                "labels": [{ "primary": true, "message": null, "span": span }],
+                "children": [],
+                "suggestions": [],
+            })
+        );
+    }
+
+    #[test]
+    fn lines() {
+        let source = Source::new("main.lam", "a b");
+        let mut reader = source.reader();
+        let mut diagnostics: Diagnostics = Diagnostics::new();
+        for _ in 0 .. 2 {
+            reader.mark();
+            reader.next();
+            diagnostics.raise(Unexpected { span: reader.span() });
+            reader.next();
+        }
+        let mut output = Vec::new();
+        write_lines(&mut output, &diagnostics).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        let second: Value = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(second["message"], "unexpected b");
+        assert_eq!(second["primary_span"]["start"]["column"], 3);
+    }
+
+    #[test]
+    fn emitter() {
+        let source = Source::new("main.lam", "a");
+        let mut reader = source.reader();
+        reader.mark();
+        reader.next();
+        let mut emitter = JsonEmitter::new(Vec::new());
+        emitter
+            .emit(Level::Error, &Unexpected { span: reader.span() })
+            .unwrap();
+        let output = String::from_utf8(emitter.into_inner()).unwrap();
+        let value: Value = serde_json::from_str(output.trim_end()).unwrap();
+        assert_eq!(value["level"], "error");
+        assert_eq!(value["message"], "unexpected a");
+    }
+}
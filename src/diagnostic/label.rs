@@ -0,0 +1,52 @@
+//! This module provides labeled spans, i.e. spans attached to a message,
+//! used to annotate specific places of a diagnostic.
+
+use crate::source::Span;
+
+/// Style of a [`Label`], i.e. whether it marks the primary place of a
+/// diagnostic or just some related place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LabelStyle {
+    /// The main place of the diagnostic.
+    Primary,
+    /// Some other place related to the diagnostic.
+    Secondary,
+}
+
+/// A span together with an optional message annotating it, such as "opening
+/// parenthesis here" or "expected `)` here".
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Label {
+    /// The span being annotated.
+    pub span: Span,
+    /// Whether this label is primary or secondary.
+    pub style: LabelStyle,
+    /// The message annotating the span, if any.
+    pub message: Option<String>,
+}
+
+impl Label {
+    /// Creates a primary label with no message.
+    pub fn primary(span: Span) -> Self {
+        Self { span, style: LabelStyle::Primary, message: None }
+    }
+
+    /// Creates a secondary label with no message.
+    pub fn secondary(span: Span) -> Self {
+        Self { span, style: LabelStyle::Secondary, message: None }
+    }
+
+    /// Sets the message of this label.
+    pub fn with_message<S>(mut self, message: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.message = Some(message.into());
+        self
+    }
+
+    /// Returns whether this is a primary label.
+    pub fn is_primary(&self) -> bool {
+        self.style == LabelStyle::Primary
+    }
+}
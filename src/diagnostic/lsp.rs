@@ -0,0 +1,305 @@
+//! This module provides conversion of diagnostics into the shapes of the
+//! [Language Server Protocol](https://microsoft.github.io/language-server-protocol/),
+//! as JSON values, for language servers built on top of this crate. Only
+//! available with the `lsp` feature.
+//!
+//! - Locations become `Position`s and spans become `Range`s. Lines and
+//!   characters are 0-based, with characters counted in UTF-16 code units
+//!   (the protocol's default position encoding), not in string segments.
+//! - [`Level::Error`] and [`Level::Bug`] map to the `Error` severity,
+//!   [`Level::Warning`] to `Warning`, [`Level::Note`] to `Information` and
+//!   [`Level::Help`] to `Hint`.
+//! - The primary span becomes the range of the diagnostic, while secondary
+//!   labels and children with spans become its `relatedInformation`.
+//!   Children without spans are appended to the message.
+//! - [`Source::name`] is used as the document URI, so sources should be named
+//!   after the URIs the client uses, e.g. `file:///home/user/main.lam`.
+//!
+//! Diagnostics without a primary span have no range, and thus cannot be
+//! converted.
+
+use super::{Diagnostic, Diagnostics, Level};
+use crate::source::{Location, Source, Span};
+use serde_json::{json, Map, Value};
+
+/// `DiagnosticSeverity` of errors.
+pub const SEVERITY_ERROR: u8 = 1;
+
+/// `DiagnosticSeverity` of warnings.
+pub const SEVERITY_WARNING: u8 = 2;
+
+/// `DiagnosticSeverity` of informational messages.
+pub const SEVERITY_INFORMATION: u8 = 3;
+
+/// `DiagnosticSeverity` of hints.
+pub const SEVERITY_HINT: u8 = 4;
+
+/// Converts a location into an LSP `Position`.
+pub fn position(location: &Location) -> Value {
+    json!({
+        "line": location.line(),
+        "character": location.utf16_column(),
+    })
+}
+
+/// Converts a span into an LSP `Range`.
+pub fn range(span: &Span) -> Value {
+    json!({
+        "start": position(&span.start()),
+        "end": position(&span.end()),
+    })
+}
+
+/// Converts a span into an LSP `Location`, i.e. the URI of its source and
+/// its range.
+pub fn location(span: &Span) -> Value {
+    json!({
+        "uri": span.source().name(),
+        "range": range(span),
+    })
+}
+
+/// Maps a level into an LSP `DiagnosticSeverity`.
+pub fn severity(level: Level) -> u8 {
+    match level {
+        Level::Help => SEVERITY_HINT,
+        Level::Note => SEVERITY_INFORMATION,
+        Level::Warning => SEVERITY_WARNING,
+        Level::Error | Level::Bug => SEVERITY_ERROR,
+    }
+}
+
+/// Converts a single diagnostic into an LSP `Diagnostic`, or `None` if it
+/// has no primary span.
+pub fn to_diagnostic<D>(diagnostic: &D) -> Option<Value>
+where
+    D: Diagnostic + ?Sized,
+{
+    leveled_to_diagnostic(diagnostic.level(), diagnostic)
+}
+
+/// Converts the diagnostics whose primary span is in the given source into
+/// LSP `PublishDiagnosticsParams`, with the levels given by the collection's
+/// policy. Diagnostics of a document are published all at once, so an empty
+/// list clears the previous ones.
+pub fn publish_params<D>(source: &Source, diagnostics: &Diagnostics<D>) -> Value
+where
+    D: Diagnostic + ?Sized,
+{
+    let converted = diagnostics
+        .iter_leveled()
+        .filter(|&(_, diagnostic)| {
+            diagnostic
+                .primary_span()
+                .is_some_and(|span| span.source() == source)
+        })
+        .filter_map(|(level, diagnostic)| {
+            leveled_to_diagnostic(level, diagnostic)
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "uri": source.name(),
+        "diagnostics": converted,
+    })
+}
+
+/// Converts a single diagnostic into an LSP `Diagnostic`, as if it had the
+/// given level.
+fn leveled_to_diagnostic<D>(level: Level, diagnostic: &D) -> Option<Value>
+where
+    D: Diagnostic + ?Sized,
+{
+    let primary_span = diagnostic.primary_span()?;
+    let mut message = diagnostic.to_string();
+    let mut related = Vec::new();
+    for label in diagnostic.labels() {
+        if !label.is_primary() {
+            let label_message = label.message.unwrap_or_default();
+            related.push(related_information(&label.span, &label_message));
+        }
+    }
+    for child in diagnostic.children() {
+        let child_message = format!("{}: {}", child.kind, child.message);
+        match &child.span {
+            Some(span) => {
+                related.push(related_information(span, &child_message))
+            },
+            None => {
+                message.push('\n');
+                message.push_str(&child_message);
+            },
+        }
+    }
+
+    let mut converted = Map::new();
+    converted.insert("range".into(), range(&primary_span));
+    converted.insert("severity".into(), json!(severity(level)));
+    if let Some(code) = diagnostic.code() {
+        converted.insert("code".into(), json!(code));
+    }
+    converted.insert("message".into(), json!(message));
+    if !related.is_empty() {
+        converted.insert("relatedInformation".into(), Value::Array(related));
+    }
+    Some(Value::Object(converted))
+}
+
+/// Converts a span and a message into an LSP
+/// `DiagnosticRelatedInformation`.
+fn related_information(span: &Span, message: &str) -> Value {
+    json!({
+        "location": location(span),
+        "message": message,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{position, publish_params, to_diagnostic};
+    use crate::{
+        diagnostic::{Child, Diagnostic, Diagnostics, Label, Level},
+        source::{Source, Span},
+    };
+    use serde_json::json;
+    use std::fmt;
+
+    #[derive(Debug)]
+    struct Mismatched {
+        open: Span,
+        close: Span,
+    }
+
+    impl fmt::Display for Mismatched {
+        fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmtr, "mismatched `{}`", self.close.as_str())
+        }
+    }
+
+    impl Diagnostic for Mismatched {
+        fn level(&self) -> Level {
+            Level::Error
+        }
+
+        fn primary_span(&self) -> Option<Span> {
+            Some(self.close.clone())
+        }
+
+        fn code(&self) -> Option<&str> {
+            Some("E0005")
+        }
+
+        fn labels<'this>(
+            &'this self,
+        ) -> Box<dyn Iterator<Item = Label> + Send + Sync + 'this> {
+            let close = Label::primary(self.close.clone());
+            let open =
+                Label::secondary(self.open.clone()).with_message("opened here");
+            Box::new(vec![close, open].into_iter())
+        }
+
+        fn children<'this>(
+            &'this self,
+        ) -> Box<dyn Iterator<Item = Child> + Send + Sync + 'this> {
+            let children = vec![
+                Child::note("brackets must match"),
+                Child::help("use `)`").with_span(self.close.clone()),
+            ];
+            Box::new(children.into_iter())
+        }
+    }
+
+    #[derive(Debug)]
+    struct Unused {
+        span: Option<Span>,
+    }
+
+    impl fmt::Display for Unused {
+        fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmtr, "unused variable")
+        }
+    }
+
+    impl Diagnostic for Unused {
+        fn level(&self) -> Level {
+            Level::Warning
+        }
+
+        fn primary_span(&self) -> Option<Span> {
+            self.span.clone()
+        }
+    }
+
+    #[test]
+    fn utf16_positions() {
+        let source = Source::new("file:///main.lam", "x\n\u{1d49c}e\u{301} y");
+        let location = source.span(4, 1).start();
+        assert_eq!(location.line_column(), (1, 2));
+        assert_eq!(location.char_column(), 3);
+        assert_eq!(position(&location), json!({ "line": 1, "character": 4 }));
+    }
+
+    #[test]
+    fn diagnostic() {
+        let source = Source::new("file:///main.lam", "(x \u{1d49c}]");
+        let diagnostic = Mismatched {
+            open: source.span(0, 1),
+            close: source.span(4, 1),
+        };
+        let range = |start, end| {
+            json!({
+                "start": { "line": 0, "character": start },
+                "end": { "line": 0, "character": end },
+            })
+        };
+        assert_eq!(
+            to_diagnostic(&diagnostic),
+            Some(json!({
+                "range": range(5, 6),
+                "severity": 1,
+                "code": "E0005",
+                "message": "mismatched `]`\nnote: brackets must match",
+                "relatedInformation": [
+                    {
+                        "location": {
+                            "uri": "file:///main.lam",
+                            "range": range(0, 1),
+                        },
+                        "message": "opened here",
+                    },
+                    {
+                        "location": {
+                            "uri": "file:///main.lam",
+                            "range": range(5, 6),
+                        },
+                        "message": "help: use `)`",
+                    },
+                ],
+            }))
+        );
+        assert_eq!(to_diagnostic(&Unused { span: None }), None);
+    }
+
+    #[test]
+    fn publish() {
+        let source = Source::new("file:///main.lam", "x y");
+        let other = Source::new("file:///other.lam", "z");
+        let mut diagnostics: Diagnostics = Diagnostics::new();
+        diagnostics.raise(Unused { span: Some(source.span(2, 1)) });
+        diagnostics.raise(Unused { span: Some(other.span(0, 1)) });
+        diagnostics.raise(Unused { span: None });
+        assert_eq!(
+            publish_params(&source, &diagnostics),
+            json!({
+                "uri": "file:///main.lam",
+                "diagnostics": [{
+                    "range": {
+                        "start": { "line": 0, "character": 2 },
+                        "end": { "line": 0, "character": 3 },
+                    },
+                    "severity": 2,
+                    "message": "unused variable",
+                }],
+            })
+        );
+    }
+}
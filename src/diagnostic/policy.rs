@@ -0,0 +1,323 @@
+//! This module provides a policy remapping levels of diagnostics, such as the
+//! one configured by command-line flags like `-W unused -D shadowing`.
+
+use super::{Diagnostic, Level};
+use std::{
+    any::{Any, TypeId},
+    collections::{hash_map, HashMap},
+    fmt,
+};
+
+/// Level configured for a kind of diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LintLevel {
+    /// Diagnostics are dropped.
+    Allow,
+    /// Diagnostics are warnings.
+    Warn,
+    /// Diagnostics are hard errors.
+    Deny,
+    /// Diagnostics are hard errors, and later settings cannot change that.
+    Forbid,
+}
+
+impl LintLevel {
+    /// Level that diagnostics get under this lint level, or `None` if they are
+    /// dropped.
+    pub fn level(self) -> Option<Level> {
+        match self {
+            LintLevel::Allow => None,
+            LintLevel::Warn => Some(Level::Warning),
+            LintLevel::Deny | LintLevel::Forbid => Some(Level::Error),
+        }
+    }
+}
+
+impl fmt::Display for LintLevel {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        fmtr.write_str(match self {
+            LintLevel::Allow => "allow",
+            LintLevel::Warn => "warn",
+            LintLevel::Deny => "deny",
+            LintLevel::Forbid => "forbid",
+        })
+    }
+}
+
+/// A policy remapping the levels of diagnostics raised into a
+/// [`Diagnostics`](super::Diagnostics) collection, or dropping them.
+///
+/// Lint levels can be set per [code](Diagnostic::code) or per diagnostic
+/// type. A setting for the type of a diagnostic takes precedence over a
+/// setting for its code, unless either of them is [`LintLevel::Forbid`],
+/// which always wins. Once a code or type is forbidden, later settings for
+/// it are ignored. Additionally, the "deny warnings" mode promotes every
+/// warning, remapped or not, to a hard error.
+///
+/// Diagnostics whose own level is [`Level::Error`] are hard errors and are
+/// never remapped nor dropped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Policy {
+    /// Lint levels indexed by diagnostic code.
+    codes: HashMap<String, LintLevel>,
+    /// Lint levels indexed by diagnostic type.
+    types: HashMap<TypeId, LintLevel>,
+    /// Whether warnings are promoted to hard errors.
+    deny_warnings: bool,
+}
+
+impl Policy {
+    /// Creates a policy that keeps every diagnostic at its own level.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the lint level of diagnostics with the given code. Ignored if the
+    /// code has been forbidden.
+    pub fn set_code<C>(&mut self, code: C, level: LintLevel) -> &mut Self
+    where
+        C: Into<String>,
+    {
+        Self::set(self.codes.entry(code.into()), level);
+        self
+    }
+
+    /// Sets the lint level of diagnostics of type `T`. Ignored if the type has
+    /// been forbidden.
+    pub fn set_type<T>(&mut self, level: LintLevel) -> &mut Self
+    where
+        T: Diagnostic,
+    {
+        Self::set(self.types.entry(TypeId::of::<T>()), level);
+        self
+    }
+
+    /// Lint level set for the given code, if any.
+    pub fn code_level(&self, code: &str) -> Option<LintLevel> {
+        self.codes.get(code).copied()
+    }
+
+    /// Lint level set for diagnostics of type `T`, if any.
+    pub fn type_level<T>(&self) -> Option<LintLevel>
+    where
+        T: Diagnostic,
+    {
+        self.types.get(&TypeId::of::<T>()).copied()
+    }
+
+    /// Returns whether warnings are promoted to hard errors.
+    pub fn deny_warnings(&self) -> bool {
+        self.deny_warnings
+    }
+
+    /// Sets whether warnings are promoted to hard errors.
+    pub fn set_deny_warnings(&mut self, deny: bool) -> &mut Self {
+        self.deny_warnings = deny;
+        self
+    }
+
+    /// Returns whether the given diagnostic is forbidden by this policy,
+    /// either by its type or by its code.
+    pub fn forbids<D>(&self, diagnostic: &D) -> bool
+    where
+        D: Diagnostic + ?Sized,
+    {
+        let by_type = self.types.get(&Any::type_id(diagnostic)).copied();
+        let by_code = diagnostic.code().and_then(|code| self.code_level(code));
+        by_type == Some(LintLevel::Forbid) || by_code == Some(LintLevel::Forbid)
+    }
+
+    /// Computes the effective level of the given diagnostic under this
+    /// policy, or `None` if it should be dropped.
+    pub fn resolve<D>(&self, diagnostic: &D) -> Option<Level>
+    where
+        D: Diagnostic + ?Sized,
+    {
+        let own = diagnostic.level();
+        if own >= Level::Error {
+            return Some(own);
+        }
+        let by_type = self.types.get(&Any::type_id(diagnostic)).copied();
+        let by_code = diagnostic.code().and_then(|code| self.code_level(code));
+        let lint = if by_type == Some(LintLevel::Forbid) {
+            by_type
+        } else if by_code == Some(LintLevel::Forbid) {
+            by_code
+        } else {
+            by_type.or(by_code)
+        };
+        let level = match lint {
+            Some(lint) => lint.level()?,
+            None => own,
+        };
+        if self.deny_warnings && level == Level::Warning {
+            Some(Level::Error)
+        } else {
+            Some(level)
+        }
+    }
+
+    /// Sets a lint level in the given entry, unless it is forbidden.
+    fn set<K>(entry: hash_map::Entry<K, LintLevel>, level: LintLevel) {
+        match entry {
+            hash_map::Entry::Vacant(entry) => {
+                entry.insert(level);
+            },
+            hash_map::Entry::Occupied(mut entry) => {
+                if *entry.get() != LintLevel::Forbid {
+                    entry.insert(level);
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LintLevel, Policy};
+    use crate::{
+        diagnostic::{Diagnostic, Diagnostics, Level},
+        source::Span,
+    };
+    use std::fmt;
+
+    #[derive(Debug)]
+    struct Unused;
+
+    impl fmt::Display for Unused {
+        fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmtr, "unused variable")
+        }
+    }
+
+    impl Diagnostic for Unused {
+        fn level(&self) -> Level {
+            Level::Warning
+        }
+
+        fn primary_span(&self) -> Option<Span> {
+            None
+        }
+
+        fn code(&self) -> Option<&str> {
+            Some("unused")
+        }
+    }
+
+    #[derive(Debug)]
+    struct Shadowing;
+
+    impl fmt::Display for Shadowing {
+        fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmtr, "shadowed variable")
+        }
+    }
+
+    impl Diagnostic for Shadowing {
+        fn level(&self) -> Level {
+            Level::Note
+        }
+
+        fn primary_span(&self) -> Option<Span> {
+            None
+        }
+
+        fn code(&self) -> Option<&str> {
+            Some("shadowing")
+        }
+    }
+
+    #[derive(Debug)]
+    struct Syntax;
+
+    impl fmt::Display for Syntax {
+        fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmtr, "syntax error")
+        }
+    }
+
+    impl Diagnostic for Syntax {
+        fn level(&self) -> Level {
+            Level::Error
+        }
+
+        fn primary_span(&self) -> Option<Span> {
+            None
+        }
+
+        fn code(&self) -> Option<&str> {
+            Some("syntax")
+        }
+    }
+
+    #[test]
+    fn remap_by_code_and_type() {
+        let mut policy = Policy::new();
+        policy
+            .set_code("unused", LintLevel::Allow)
+            .set_code("shadowing", LintLevel::Deny)
+            .set_code("syntax", LintLevel::Allow);
+        assert_eq!(policy.resolve(&Unused), None);
+        assert_eq!(policy.resolve(&Shadowing), Some(Level::Error));
+        assert_eq!(policy.resolve(&Syntax), Some(Level::Error));
+        policy.set_type::<Shadowing>(LintLevel::Warn);
+        assert_eq!(policy.resolve(&Shadowing), Some(Level::Warning));
+    }
+
+    #[test]
+    fn forbid() {
+        let mut policy = Policy::new();
+        policy
+            .set_code("unused", LintLevel::Forbid)
+            .set_code("unused", LintLevel::Allow);
+        assert_eq!(policy.code_level("unused"), Some(LintLevel::Forbid));
+        assert_eq!(policy.resolve(&Unused), Some(Level::Error));
+        assert!(policy.forbids(&Unused));
+        assert!(!policy.forbids(&Shadowing));
+    }
+
+    #[test]
+    fn forbid_across_code_and_type() {
+        let mut policy = Policy::new();
+        policy
+            .set_code("unused", LintLevel::Forbid)
+            .set_type::<Unused>(LintLevel::Allow);
+        assert_eq!(policy.resolve(&Unused), Some(Level::Error));
+        assert!(policy.forbids(&Unused));
+
+        let mut policy = Policy::new();
+        policy
+            .set_type::<Shadowing>(LintLevel::Forbid)
+            .set_code("shadowing", LintLevel::Allow);
+        assert_eq!(policy.resolve(&Shadowing), Some(Level::Error));
+        assert!(policy.forbids(&Shadowing));
+    }
+
+    #[test]
+    fn deny_warnings() {
+        let mut policy = Policy::new();
+        policy.set_deny_warnings(true).set_code("shadowing", LintLevel::Warn);
+        assert_eq!(policy.resolve(&Unused), Some(Level::Error));
+        assert_eq!(policy.resolve(&Shadowing), Some(Level::Error));
+    }
+
+    #[test]
+    fn diagnostics_levels() {
+        let mut policy = Policy::new();
+        policy.set_type::<Unused>(LintLevel::Allow).set_deny_warnings(true);
+        let mut diagnostics: Diagnostics = Diagnostics::with_policy(policy);
+        diagnostics.raise(Unused);
+        diagnostics.raise(Shadowing);
+        assert!(diagnostics.is_ok());
+        assert_eq!(diagnostics.iter().count(), 1);
+
+        diagnostics.policy_mut().set_code("shadowing", LintLevel::Warn);
+        diagnostics.raise(Shadowing);
+        assert!(diagnostics.is_err());
+        let levels = diagnostics
+            .iter_leveled()
+            .map(|(level, _)| level)
+            .collect::<Vec<_>>();
+        assert_eq!(levels, [Level::Note, Level::Error]);
+    }
+}
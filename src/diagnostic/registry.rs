@@ -0,0 +1,119 @@
+//! This module provides a registry of long-form explanations of diagnostic
+//! codes.
+
+use std::collections::{btree_map, BTreeMap};
+
+/// A registry of long-form explanations of diagnostic codes (see
+/// [`Diagnostic::code`](super::Diagnostic::code)), such as the ones a
+/// command-line interface prints for `--explain E0042`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Registry {
+    /// Explanations indexed by their code.
+    explanations: BTreeMap<String, String>,
+}
+
+impl Registry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the explanation of the given code.
+    ///
+    /// # Panics
+    /// Panics if the code has already been registered.
+    pub fn register<C, E>(&mut self, code: C, explanation: E) -> &mut Self
+    where
+        C: Into<String>,
+        E: Into<String>,
+    {
+        match self.explanations.entry(code.into()) {
+            btree_map::Entry::Vacant(entry) => {
+                entry.insert(explanation.into());
+            },
+            btree_map::Entry::Occupied(entry) => {
+                panic!("code {} has already been registered", entry.key())
+            },
+        }
+        self
+    }
+
+    /// Finds the explanation of the given code, if registered.
+    pub fn explain(&self, code: &str) -> Option<&str> {
+        self.explanations.get(code).map(String::as_str)
+    }
+
+    /// Returns whether the given code has been registered.
+    pub fn contains(&self, code: &str) -> bool {
+        self.explanations.contains_key(code)
+    }
+
+    /// Number of registered codes.
+    pub fn len(&self) -> usize {
+        self.explanations.len()
+    }
+
+    /// Returns whether no code has been registered.
+    pub fn is_empty(&self) -> bool {
+        self.explanations.is_empty()
+    }
+
+    /// Creates an iterator over registered codes and their explanations,
+    /// ordered by code.
+    pub fn iter(&self) -> RegistryIter<'_> {
+        RegistryIter { inner: self.explanations.iter() }
+    }
+}
+
+impl<'registry> IntoIterator for &'registry Registry {
+    type Item = (&'registry str, &'registry str);
+    type IntoIter = RegistryIter<'registry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over codes and explanations of a [`Registry`].
+#[derive(Debug, Clone)]
+pub struct RegistryIter<'registry> {
+    /// Inner map iterator.
+    inner: btree_map::Iter<'registry, String, String>,
+}
+
+impl<'registry> Iterator for RegistryIter<'registry> {
+    type Item = (&'registry str, &'registry str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|(code, explanation)| (code.as_str(), explanation.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Registry;
+
+    #[test]
+    fn explain() {
+        let mut registry = Registry::new();
+        registry
+            .register("E0002", "A token was found where another was expected.")
+            .register("E0001", "The source contains an invalid character.");
+        assert_eq!(
+            registry.explain("E0001"),
+            Some("The source contains an invalid character.")
+        );
+        assert_eq!(registry.explain("E0003"), None);
+        let codes = registry.iter().map(|(code, _)| code).collect::<Vec<_>>();
+        assert_eq!(codes, ["E0001", "E0002"]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn duplicate_code() {
+        let mut registry = Registry::new();
+        registry.register("E0001", "first").register("E0001", "second");
+    }
+}
@@ -0,0 +1,1043 @@
+//! This module provides a renderer of diagnostics for terminals, showing
+//! snippets of the source code a diagnostic refers to.
+
+use super::{Catalog, ChildKind, Diagnostic, Diagnostics, Level};
+use crate::source::{Source, Span};
+use std::{fmt, sync::Arc};
+use unicode_width::UnicodeWidthStr;
+
+/// ANSI escape sequence that resets text style.
+const RESET: &str = "\x1b[0m";
+
+/// Renders diagnostics in a human-readable format, similar to `rustc`'s:
+/// a header with the level, code and message, followed by the lines the
+/// diagnostic spans are in, with the primary span underlined with `^^^` and
+/// secondary spans underlined with `---`.
+///
+/// # Example
+/// ```text
+/// error[E0003]: unmatched opening parenthesis `(`
+///  --> main.lam:1:5
+///   |
+/// 1 | \x. (\y. (a x y
+///   |     ^
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Renderer {
+    /// Width of a tab stop, in columns.
+    tab_width: usize,
+    /// Whether ANSI colors are emitted.
+    colored: bool,
+    /// Catalog translating messages, if any.
+    catalog: Option<Arc<Catalog>>,
+    /// Locale messages are translated into.
+    locale: String,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self {
+            tab_width: 4,
+            colored: false,
+            catalog: None,
+            locale: String::new(),
+        }
+    }
+}
+
+impl Renderer {
+    /// Creates a new renderer with default settings: tab stops every 4
+    /// columns, no colors and no translation of messages.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Width of a tab stop, in columns.
+    pub fn tab_width(&self) -> usize {
+        self.tab_width
+    }
+
+    /// Sets the width of a tab stop, in columns.
+    ///
+    /// # Panics
+    /// Panics if the width is zero.
+    pub fn set_tab_width(&mut self, tab_width: usize) -> &mut Self {
+        if tab_width == 0 {
+            panic!("Tab width cannot be zero");
+        }
+        self.tab_width = tab_width;
+        self
+    }
+
+    /// Whether ANSI colors are emitted.
+    pub fn is_colored(&self) -> bool {
+        self.colored
+    }
+
+    /// Sets whether ANSI colors are emitted.
+    pub fn set_colored(&mut self, colored: bool) -> &mut Self {
+        self.colored = colored;
+        self
+    }
+
+    /// Catalog translating messages, if any.
+    pub fn catalog(&self) -> Option<&Catalog> {
+        self.catalog.as_deref()
+    }
+
+    /// Sets the catalog translating messages of diagnostics with a
+    /// [localizable message](Diagnostic::message). Without a catalog, or
+    /// without a translation, the `Display` text of diagnostics is shown.
+    /// Only the main message is translated; label messages and children are
+    /// shown as given.
+    pub fn set_catalog(&mut self, catalog: Option<Arc<Catalog>>) -> &mut Self {
+        self.catalog = catalog;
+        self
+    }
+
+    /// Locale messages are translated into.
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Sets the locale messages are translated into, e.g. `pt-BR`.
+    pub fn set_locale<S>(&mut self, locale: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.locale = locale.into();
+        self
+    }
+
+    /// Creates a type that, when displayed, renders the given diagnostic.
+    pub fn render<'this, 'diag, D>(
+        &'this self,
+        diagnostic: &'diag D,
+    ) -> Rendered<'this, 'diag, D>
+    where
+        D: Diagnostic + ?Sized,
+    {
+        Rendered { renderer: self, diagnostic }
+    }
+
+    /// Writes the given diagnostic rendered into the given output.
+    pub fn write<W, D>(&self, output: &mut W, diagnostic: &D) -> fmt::Result
+    where
+        W: fmt::Write + ?Sized,
+        D: Diagnostic + ?Sized,
+    {
+        self.write_leveled(output, diagnostic.level(), diagnostic)
+    }
+
+    /// Writes every diagnostic in the given collection, rendered, into the
+    /// given output, with the levels given by the collection's policy.
+    /// Diagnostics are separated by blank lines, and followed by a summary of
+    /// omitted errors, if the collection's error limit was exceeded.
+    pub fn write_all<W, D>(
+        &self,
+        output: &mut W,
+        diagnostics: &Diagnostics<D>,
+    ) -> fmt::Result
+    where
+        W: fmt::Write + ?Sized,
+        D: Diagnostic + ?Sized,
+    {
+        for (i, (level, diagnostic)) in diagnostics.iter_leveled().enumerate() {
+            if i > 0 {
+                writeln!(output)?;
+            }
+            self.write_leveled(output, level, diagnostic)?;
+        }
+        let omitted = diagnostics.omitted_errors();
+        if omitted > 0 {
+            if diagnostics.iter().next().is_some() {
+                writeln!(output)?;
+            }
+            self.write_omitted(output, omitted)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the summary of the given number of omitted errors into the
+    /// given output.
+    pub(super) fn write_omitted<W>(
+        &self,
+        output: &mut W,
+        count: usize,
+    ) -> fmt::Result
+    where
+        W: fmt::Write + ?Sized,
+    {
+        let plural = if count == 1 { "" } else { "s" };
+        let style = Style::Level(Level::Error);
+        self.paint(output, style, &Level::Error.to_string())?;
+        let summary = format!(": {} more error{} omitted", count, plural);
+        self.paint(output, Style::Bold, &summary)?;
+        writeln!(output)
+    }
+
+    /// Writes the given diagnostic rendered into the given output, as if it
+    /// had the given level.
+    pub(super) fn write_leveled<W, D>(
+        &self,
+        output: &mut W,
+        level: Level,
+        diagnostic: &D,
+    ) -> fmt::Result
+    where
+        W: fmt::Write + ?Sized,
+        D: Diagnostic + ?Sized,
+    {
+        let header = match diagnostic.code() {
+            Some(code) => format!("{}[{}]", level, code),
+            None => level.to_string(),
+        };
+        self.paint(output, Style::Level(level), &header)?;
+        let message = match &self.catalog {
+            Some(catalog) => catalog.localize(&self.locale, diagnostic),
+            None => diagnostic.to_string(),
+        };
+        self.paint(output, Style::Bold, &format!(": {}", message))?;
+        writeln!(output)?;
+
+        let annotations = Annotation::collect(diagnostic);
+        let gutter = Self::gutter_width(&annotations);
+        self.write_snippets(output, Style::Level(level), &annotations)?;
+
+        for child in diagnostic.children() {
+            let style = Style::Child(child.kind);
+            match child.span {
+                Some(span) => {
+                    let header = format!(": {}", child.message);
+                    self.paint(output, style, &child.kind.to_string())?;
+                    self.paint(output, Style::Bold, &header)?;
+                    writeln!(output)?;
+                    let annotation =
+                        Annotation { span, primary: true, message: None };
+                    self.write_snippets(output, style, &[annotation])?;
+                },
+                None => {
+                    write!(output, "{:1$}", "", gutter)?;
+                    self.paint(output, Style::Gutter, " =")?;
+                    output.write_char(' ')?;
+                    self.paint(output, style, &child.kind.to_string())?;
+                    writeln!(output, ": {}", child.message)?;
+                },
+            }
+        }
+
+        for suggestion in diagnostic.suggestions() {
+            let style = Style::Child(ChildKind::Help);
+            write!(output, "{:1$}", "", gutter)?;
+            self.paint(output, Style::Gutter, " =")?;
+            output.write_char(' ')?;
+            self.paint(output, style, &ChildKind::Help.to_string())?;
+            write!(output, ": {}", suggestion.message)?;
+            match suggestion.edits.as_slice() {
+                [edit] if !edit.replacement().is_empty() => {
+                    writeln!(output, ": `{}`", edit.replacement())?
+                },
+                _ => writeln!(output)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Width of the gutter needed to show the line numbers of the given
+    /// annotations.
+    fn gutter_width(annotations: &[Annotation]) -> usize {
+        let last_line = annotations
+            .iter()
+            .map(|annotation| annotation.last_line())
+            .max()
+            .unwrap_or(0);
+        (last_line + 1).to_string().len()
+    }
+
+    /// Writes the source code snippets of all annotations, grouped by source.
+    /// Primary marks are written in the given style.
+    fn write_snippets<W>(
+        &self,
+        output: &mut W,
+        primary: Style,
+        annotations: &[Annotation],
+    ) -> fmt::Result
+    where
+        W: fmt::Write + ?Sized,
+    {
+        let mut groups = Vec::<Group>::new();
+        for annotation in annotations {
+            let source = annotation.span.source();
+            match groups.iter_mut().find(|group| group.source == *source) {
+                Some(group) => group.annotations.push(annotation),
+                None => groups.push(Group {
+                    source: source.clone(),
+                    annotations: vec![annotation],
+                }),
+            }
+        }
+
+        let gutter = Self::gutter_width(annotations);
+
+        for (i, group) in groups.iter().enumerate() {
+            let anchor = group.annotations[0].span.start();
+            let (line, column) = anchor.line_column();
+            let arrow = if i == 0 { "-->" } else { ":::" };
+            write!(output, "{:1$}", "", gutter)?;
+            self.paint(output, Style::Gutter, arrow)?;
+            writeln!(
+                output,
+                " {}:{}:{}",
+                group.source.name(),
+                line + 1,
+                column + 1
+            )?;
+            self.write_gutter(output, gutter, None)?;
+            writeln!(output)?;
+
+            let mut previous = None;
+            for line in group.lines() {
+                if previous.is_some_and(|previous| line > previous + 1) {
+                    self.paint(output, Style::Gutter, "...")?;
+                    writeln!(output)?;
+                }
+                self.write_line(output, primary, group, line, gutter)?;
+                previous = Some(line);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes a single line of a source with the annotations that touch it
+    /// underlined. Primary marks are written in the given style.
+    fn write_line<W>(
+        &self,
+        output: &mut W,
+        primary: Style,
+        group: &Group,
+        line: usize,
+        gutter: usize,
+    ) -> fmt::Result
+    where
+        W: fmt::Write + ?Sized,
+    {
+        let start = group.source.line_start(line);
+        let end = group.source.line_end(line);
+
+        // Segments are sliced at the character boundaries at or before their
+        // ends: a character split into bytes is written at its last byte, and
+        // all of its bytes get its column.
+        let contents = group.source.contents();
+        let mut text = String::new();
+        let mut columns = Vec::with_capacity(end - start + 1);
+        let mut column = 0;
+        let mut offset = group.source.floor_char_offset(start);
+        for position in start .. end {
+            columns.push(column);
+            let next = group.source.floor_char_offset(position + 1);
+            let grapheme = &contents[offset .. next];
+            offset = next;
+            if grapheme == "\t" {
+                let width = self.tab_width - column % self.tab_width;
+                text.extend((0 .. width).map(|_| ' '));
+                column += width;
+            } else {
+                text.push_str(grapheme);
+                column += grapheme.width();
+            }
+        }
+        columns.push(column);
+
+        self.write_gutter(output, gutter, Some(line))?;
+        if !text.is_empty() {
+            write!(output, " {}", text)?;
+        }
+        writeln!(output)?;
+
+        let mut marks = Vec::<Option<bool>>::new();
+        let mut labels = Vec::new();
+        let mut touched = false;
+        // Secondary first, so primary marks override them.
+        let ordered = group
+            .annotations
+            .iter()
+            .filter(|annotation| !annotation.primary)
+            .chain(group.annotations.iter().filter(|ann| ann.primary));
+        for annotation in ordered {
+            if let Some((from, to)) =
+                annotation.columns(line, start, end, &columns)
+            {
+                touched = true;
+                if marks.len() < to {
+                    marks.resize(to, None);
+                }
+                for mark in &mut marks[from .. to] {
+                    *mark = Some(annotation.primary);
+                }
+                if let Some(message) = annotation.message.as_ref() {
+                    if line == annotation.last_line() {
+                        let style = annotation.style(primary);
+                        labels.push((from, to, message.as_str(), style));
+                    }
+                }
+            }
+        }
+
+        if touched {
+            labels.sort_by_key(|&(from, to, _, _)| (from, to));
+            self.write_gutter(output, gutter, None)?;
+            output.write_char(' ')?;
+            self.write_marks(output, primary, &marks)?;
+            // The rightmost label fits in the marks row if nothing is marked
+            // after it.
+            if let Some(&(_, to, message, style)) = labels.last() {
+                if to >= marks.len() {
+                    output.write_char(' ')?;
+                    self.paint(output, style, message)?;
+                    labels.pop();
+                }
+            }
+            writeln!(output)?;
+
+            // Remaining labels are placed below, connected to their spans by
+            // vertical bars, rightmost first.
+            if !labels.is_empty() {
+                let bars = labels
+                    .iter()
+                    .map(|&(from, _, _, style)| (from, "|", style))
+                    .collect::<Vec<_>>();
+                self.write_row(output, gutter, &bars)?;
+            }
+            while let Some((from, _, message, style)) = labels.pop() {
+                let mut row = labels
+                    .iter()
+                    .filter(|&&(other, _, _, _)| other < from)
+                    .map(|&(other, _, _, style)| (other, "|", style))
+                    .collect::<Vec<_>>();
+                row.push((from, message, style));
+                self.write_row(output, gutter, &row)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes a row below a source line, made of the given texts placed at
+    /// the given display columns. Columns must be sorted and texts must not
+    /// overlap.
+    fn write_row<W>(
+        &self,
+        output: &mut W,
+        gutter: usize,
+        cells: &[(usize, &str, Style)],
+    ) -> fmt::Result
+    where
+        W: fmt::Write + ?Sized,
+    {
+        self.write_gutter(output, gutter, None)?;
+        output.write_char(' ')?;
+        let mut column = 0;
+        for &(target, text, style) in cells {
+            if target > column {
+                write!(output, "{:1$}", "", target - column)?;
+                column = target;
+            }
+            self.paint(output, style, text)?;
+            column += text.width();
+        }
+        writeln!(output)
+    }
+
+    /// Writes a row of underline marks, where `Some(true)` is a primary
+    /// mark, `Some(false)` a secondary mark and `None` a blank. Primary marks
+    /// are written in the given style.
+    fn write_marks<W>(
+        &self,
+        output: &mut W,
+        primary: Style,
+        marks: &[Option<bool>],
+    ) -> fmt::Result
+    where
+        W: fmt::Write + ?Sized,
+    {
+        let mut rest = marks;
+        while let Some(&first) = rest.first() {
+            let run = rest.iter().take_while(|&&mark| mark == first).count();
+            let (style, ch) = match first {
+                Some(true) => (Some(primary), '^'),
+                Some(false) => (Some(Style::Gutter), '-'),
+                None => (None, ' '),
+            };
+            let text = (0 .. run).map(|_| ch).collect::<String>();
+            match style {
+                Some(style) => self.paint(output, style, &text)?,
+                None => output.write_str(&text)?,
+            }
+            rest = &rest[run ..];
+        }
+        Ok(())
+    }
+
+    /// Writes the gutter, with the line number (if any) right-aligned.
+    fn write_gutter<W>(
+        &self,
+        output: &mut W,
+        gutter: usize,
+        line: Option<usize>,
+    ) -> fmt::Result
+    where
+        W: fmt::Write + ?Sized,
+    {
+        let text = match line {
+            Some(line) => format!("{:>1$} |", line + 1, gutter),
+            None => format!("{:1$} |", "", gutter),
+        };
+        self.paint(output, Style::Gutter, &text)
+    }
+
+    /// Writes the given text with the given style, if colors are enabled.
+    fn paint<W>(&self, output: &mut W, style: Style, text: &str) -> fmt::Result
+    where
+        W: fmt::Write + ?Sized,
+    {
+        if self.colored {
+            write!(output, "{}{}{}", style.escape(), text, RESET)
+        } else {
+            output.write_str(text)
+        }
+    }
+}
+
+/// A type that, when displayed, renders a diagnostic using a [`Renderer`].
+///
+/// See [`Renderer::render`] to create it.
+#[derive(Debug)]
+pub struct Rendered<'renderer, 'diag, D>
+where
+    D: Diagnostic + ?Sized,
+{
+    /// The renderer being used.
+    renderer: &'renderer Renderer,
+    /// The diagnostic being rendered.
+    diagnostic: &'diag D,
+}
+
+impl<'renderer, 'diag, D> fmt::Display for Rendered<'renderer, 'diag, D>
+where
+    D: Diagnostic + ?Sized,
+{
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        self.renderer.write(fmtr, self.diagnostic)
+    }
+}
+
+/// Text style used when colors are enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+    /// Style of a level's name and primary marks.
+    Level(Level),
+    /// Style of a child's kind and primary marks.
+    Child(ChildKind),
+    /// Style of the gutter and secondary marks.
+    Gutter,
+    /// Just bold text.
+    Bold,
+}
+
+impl Style {
+    /// ANSI escape sequence of this style.
+    fn escape(self) -> &'static str {
+        match self {
+            Style::Level(Level::Help) => "\x1b[1;36m",
+            Style::Level(Level::Note) => "\x1b[1;32m",
+            Style::Level(Level::Warning) => "\x1b[1;33m",
+            Style::Level(Level::Error) => "\x1b[1;31m",
+            Style::Level(Level::Bug) => "\x1b[1;35m",
+            Style::Child(ChildKind::Note) => "\x1b[1;32m",
+            Style::Child(ChildKind::Help) => "\x1b[1;36m",
+            Style::Gutter => "\x1b[1;34m",
+            Style::Bold => "\x1b[1m",
+        }
+    }
+}
+
+/// A span to be underlined in the rendered output.
+#[derive(Debug, Clone)]
+struct Annotation {
+    /// The span underlined.
+    span: Span,
+    /// Whether this is a primary span.
+    primary: bool,
+    /// Message of the label, if any.
+    message: Option<String>,
+}
+
+impl Annotation {
+    /// Collects the annotations of a diagnostic from its labels, primary
+    /// labels first.
+    fn collect<D>(diagnostic: &D) -> Vec<Self>
+    where
+        D: Diagnostic + ?Sized,
+    {
+        let mut annotations = diagnostic
+            .labels()
+            .map(|label| Annotation {
+                primary: label.is_primary(),
+                span: label.span,
+                message: label.message,
+            })
+            .collect::<Vec<_>>();
+        annotations.sort_by_key(|annotation| !annotation.primary);
+        annotations
+    }
+
+    /// Style of this annotation's marks and message, given the style of
+    /// primary marks.
+    fn style(&self, primary: Style) -> Style {
+        if self.primary {
+            primary
+        } else {
+            Style::Gutter
+        }
+    }
+
+    /// Line where this annotation starts.
+    fn first_line(&self) -> usize {
+        self.span.start().line()
+    }
+
+    /// Line where this annotation ends. A newline at the end of the span
+    /// belongs to the line it finishes.
+    fn last_line(&self) -> usize {
+        if self.span.is_empty() {
+            self.first_line()
+        } else {
+            let last = self.span.start().position() + self.span.len() - 1;
+            self.span.source().line(last)
+        }
+    }
+
+    /// Computes the display columns `[from, to)` this annotation covers in
+    /// the given line, whose segments range from `start` to `end` and whose
+    /// display columns are given by `columns`. Returns `None` if the
+    /// annotation does not touch this line.
+    fn columns(
+        &self,
+        line: usize,
+        start: usize,
+        end: usize,
+        columns: &[usize],
+    ) -> Option<(usize, usize)> {
+        if line < self.first_line() || line > self.last_line() {
+            return None;
+        }
+        let span_start = self.span.start().position();
+        let span_end = span_start + self.span.len();
+        let low = span_start.max(start).min(end);
+        let mut high = span_end.min(end).max(low);
+        // A span ending inside a character covers all of it.
+        let source = self.span.source();
+        while high < end
+            && source.floor_char_offset(high) != source.ceil_char_offset(high)
+        {
+            high += 1;
+        }
+        let from = columns[low - start];
+        let to = columns[high - start].max(from + 1);
+        Some((from, to))
+    }
+}
+
+/// Annotations of the same source.
+#[derive(Debug)]
+struct Group<'ann> {
+    /// The source shared by the annotations.
+    source: Source,
+    /// Annotations in this group.
+    annotations: Vec<&'ann Annotation>,
+}
+
+impl<'ann> Group<'ann> {
+    /// Maximum number of lines of an annotation shown without elision.
+    const MAX_LINES: usize = 4;
+
+    /// Sorted and deduplicated list of lines to be shown. Lines in the middle
+    /// of long annotations are elided.
+    fn lines(&self) -> Vec<usize> {
+        let mut lines = Vec::new();
+        for annotation in &self.annotations {
+            let first = annotation.first_line();
+            let last = annotation.last_line();
+            if last - first < Self::MAX_LINES {
+                lines.extend(first ..= last);
+            } else {
+                let half = Self::MAX_LINES / 2;
+                lines.extend(first .. first + half);
+                lines.extend(last + 1 - half ..= last);
+            }
+        }
+        lines.sort_unstable();
+        lines.dedup();
+        lines
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Renderer;
+    use crate::{
+        diagnostic::{
+            Applicability,
+            Catalog,
+            Child,
+            Diagnostic,
+            Diagnostics,
+            Label,
+            Level,
+            Message,
+            Policy,
+            Suggestion,
+        },
+        source::{Edit, Segmentation, Source, Span},
+    };
+    use std::{fmt, sync::Arc};
+
+    #[derive(Debug)]
+    struct Dummy {
+        level: Level,
+        primary: Option<Span>,
+        secondary: Vec<Span>,
+    }
+
+    impl fmt::Display for Dummy {
+        fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmtr, "something is wrong")
+        }
+    }
+
+    impl Diagnostic for Dummy {
+        fn level(&self) -> Level {
+            self.level
+        }
+
+        fn primary_span(&self) -> Option<Span> {
+            self.primary.clone()
+        }
+
+        fn secondary_spans<'this>(
+            &'this self,
+        ) -> Option<Box<dyn Iterator<Item = Span> + Send + Sync + 'this>>
+        {
+            Some(Box::new(self.secondary.iter().cloned()))
+        }
+    }
+
+    #[derive(Debug)]
+    struct Localized {
+        span: Span,
+    }
+
+    impl fmt::Display for Localized {
+        fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmtr, "unused variable `{}`", self.span.as_str())
+        }
+    }
+
+    impl Diagnostic for Localized {
+        fn level(&self) -> Level {
+            Level::Warning
+        }
+
+        fn primary_span(&self) -> Option<Span> {
+            Some(self.span.clone())
+        }
+
+        fn message(&self) -> Option<Message> {
+            Some(Message::new("unused").with_arg("name", self.span.as_str()))
+        }
+
+        fn labels<'this>(
+            &'this self,
+        ) -> Box<dyn Iterator<Item = Label> + Send + Sync + 'this> {
+            let label = Label::primary(self.span.clone());
+            Box::new(Some(label.with_message("never read")).into_iter())
+        }
+
+        fn children<'this>(
+            &'this self,
+        ) -> Box<dyn Iterator<Item = Child> + Send + Sync + 'this> {
+            Box::new(Some(Child::help("remove it")).into_iter())
+        }
+    }
+
+    #[derive(Debug)]
+    struct Labeled {
+        code: Option<&'static str>,
+        labels: Vec<Label>,
+        children: Vec<Child>,
+        suggestions: Vec<Suggestion>,
+    }
+
+    impl fmt::Display for Labeled {
+        fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmtr, "mismatched parenthesis")
+        }
+    }
+
+    impl Diagnostic for Labeled {
+        fn level(&self) -> Level {
+            Level::Error
+        }
+
+        fn primary_span(&self) -> Option<Span> {
+            self.labels
+                .iter()
+                .find(|label| label.is_primary())
+                .map(|label| label.span.clone())
+        }
+
+        fn code(&self) -> Option<&str> {
+            self.code
+        }
+
+        fn labels<'this>(
+            &'this self,
+        ) -> Box<dyn Iterator<Item = Label> + Send + Sync + 'this> {
+            Box::new(self.labels.iter().cloned())
+        }
+
+        fn children<'this>(
+            &'this self,
+        ) -> Box<dyn Iterator<Item = Child> + Send + Sync + 'this> {
+            Box::new(self.children.iter().cloned())
+        }
+
+        fn suggestions<'this>(
+            &'this self,
+        ) -> Box<dyn Iterator<Item = Suggestion> + Send + Sync + 'this>
+        {
+            Box::new(self.suggestions.iter().cloned())
+        }
+    }
+
+    #[test]
+    fn single_line() {
+        let source = Source::new("main.lam", "\\x. (\\y. (a x y\n");
+        let diagnostic = Dummy {
+            level: Level::Error,
+            primary: Some(source.span(4, 1)),
+            secondary: vec![source.span(9, 1)],
+        };
+        let rendered = Renderer::new().render(&diagnostic).to_string();
+        assert_eq!(
+            rendered,
+            "error: something is wrong\n --> main.lam:1:5\n  |\n1 | \\x. \
+             (\\y. (a x y\n  |     ^    -\n"
+        );
+    }
+
+    #[test]
+    fn split_chars() {
+        let source =
+            Source::with_segmentation("a.bin", "é!日", Segmentation::Bytes);
+        let diagnostic = Dummy {
+            level: Level::Error,
+            primary: Some(source.span(1, 1)),
+            secondary: vec![source.span(2, 2)],
+        };
+        let rendered = Renderer::new().render(&diagnostic).to_string();
+        assert_eq!(
+            rendered,
+            "error: something is wrong\n --> a.bin:1:2\n  |\n1 | é!日\n  \
+             | ^---\n"
+        );
+    }
+
+    #[test]
+    fn no_spans() {
+        let diagnostic =
+            Dummy { level: Level::Note, primary: None, secondary: Vec::new() };
+        let rendered = Renderer::new().render(&diagnostic).to_string();
+        assert_eq!(rendered, "note: something is wrong\n");
+    }
+
+    #[test]
+    fn multi_line() {
+        let source = Source::new("a.txt", "foo(\n  bar,\n  baz)\nqux");
+        let diagnostic = Dummy {
+            level: Level::Warning,
+            primary: Some(source.span(3, 15)),
+            secondary: Vec::new(),
+        };
+        let rendered = Renderer::new().render(&diagnostic).to_string();
+        assert_eq!(
+            rendered,
+            "warning: something is wrong\n --> a.txt:1:4\n  |\n1 | foo(\n  \
+             |    ^\n2 |   bar,\n  | ^^^^^^\n3 |   baz)\n  | ^^^^^^\n"
+        );
+    }
+
+    #[test]
+    fn tabs_and_wide_graphemes() {
+        let source = Source::new("b.txt", "\tx = \"日本\" + y̆\n");
+        let diagnostic = Dummy {
+            level: Level::Error,
+            primary: Some(source.span(12, 1)),
+            secondary: vec![source.span(5, 4)],
+        };
+        let mut renderer = Renderer::new();
+        renderer.set_tab_width(2);
+        let rendered = renderer.render(&diagnostic).to_string();
+        assert_eq!(
+            rendered,
+            "error: something is wrong\n --> b.txt:1:13\n  |\n1 |   x = \
+             \"日本\" + y̆\n  |       ------   ^\n"
+        );
+    }
+
+    #[test]
+    fn end_of_input() {
+        let source = Source::new("c.txt", "abc\n");
+        let diagnostic = Dummy {
+            level: Level::Error,
+            primary: Some(source.span(4, 0)),
+            secondary: Vec::new(),
+        };
+        let rendered = Renderer::new().render(&diagnostic).to_string();
+        assert_eq!(
+            rendered,
+            "error: something is wrong\n --> c.txt:2:1\n  |\n2 |\n  | ^\n"
+        );
+    }
+
+    #[test]
+    fn labels() {
+        let source = Source::new("d.txt", "foo(bar, baz)\n");
+        let diagnostic = Labeled {
+            code: None,
+            labels: vec![
+                Label::primary(source.span(4, 3)).with_message("first"),
+                Label::secondary(source.span(9, 3)).with_message("second"),
+            ],
+            children: Vec::new(),
+            suggestions: Vec::new(),
+        };
+        let rendered = Renderer::new().render(&diagnostic).to_string();
+        assert_eq!(
+            rendered,
+            "error: mismatched parenthesis\n --> d.txt:1:5\n  |\n1 | \
+             foo(bar, baz)\n  |     ^^^  --- second\n  |     |\n  |     \
+             first\n"
+        );
+    }
+
+    #[test]
+    fn stacked_labels() {
+        let source = Source::new("e.txt", "(a b c)\n");
+        let diagnostic = Labeled {
+            code: None,
+            labels: vec![
+                Label::secondary(source.span(1, 1)).with_message("a"),
+                Label::secondary(source.span(3, 1)).with_message("b"),
+                Label::primary(source.span(0, 7)).with_message("all"),
+            ],
+            children: Vec::new(),
+            suggestions: Vec::new(),
+        };
+        let rendered = Renderer::new().render(&diagnostic).to_string();
+        assert_eq!(
+            rendered,
+            "error: mismatched parenthesis\n --> e.txt:1:1\n  |\n1 | \
+             (a b c)\n  | ^^^^^^^\n  | || |\n  | || b\n  | |a\n  | all\n"
+        );
+    }
+
+    #[test]
+    fn children() {
+        let source = Source::new("f.txt", "let x = 1;\nlet x = 2;\n");
+        let diagnostic = Labeled {
+            code: Some("E0428"),
+            labels: vec![Label::primary(source.span(15, 1))],
+            children: vec![
+                Child::note("first defined here")
+                    .with_span(source.span(4, 1)),
+                Child::help("consider renaming it"),
+            ],
+            suggestions: vec![Suggestion::new(
+                "consider a different name",
+                Applicability::MaybeIncorrect,
+            )
+            .with_edit(Edit::replace(source.span(15, 1), "y"))],
+        };
+        let rendered = Renderer::new().render(&diagnostic).to_string();
+        assert_eq!(
+            rendered,
+            "error[E0428]: mismatched parenthesis\n --> f.txt:2:5\n  |\n2 | \
+             let x = 2;\n  |     ^\nnote: first defined here\n --> \
+             f.txt:1:5\n  |\n1 | let x = 1;\n  |     ^\n  = help: consider \
+             renaming it\n  = help: consider a different name: `y`\n"
+        );
+    }
+
+    #[test]
+    fn policy_levels() {
+        let mut policy = Policy::new();
+        policy.set_deny_warnings(true);
+        let mut diagnostics: Diagnostics = Diagnostics::with_policy(policy);
+        for &level in &[Level::Note, Level::Warning] {
+            diagnostics.raise(Dummy {
+                level,
+                primary: None,
+                secondary: Vec::new(),
+            });
+        }
+        let mut rendered = String::new();
+        Renderer::new().write_all(&mut rendered, &diagnostics).unwrap();
+        assert_eq!(
+            rendered,
+            "note: something is wrong\n\nerror: something is wrong\n"
+        );
+    }
+
+    #[test]
+    fn omitted_errors() {
+        let mut diagnostics: Diagnostics = Diagnostics::new();
+        diagnostics.set_error_limit(Some(1));
+        for _ in 0 .. 3 {
+            diagnostics.raise(Dummy {
+                level: Level::Error,
+                primary: None,
+                secondary: Vec::new(),
+            });
+        }
+        assert!(diagnostics.is_exhausted());
+        assert_eq!(diagnostics.error_count(), 3);
+        let mut rendered = String::new();
+        Renderer::new().write_all(&mut rendered, &diagnostics).unwrap();
+        assert_eq!(
+            rendered,
+            "error: something is wrong\n\nerror: 2 more errors omitted\n"
+        );
+    }
+
+    #[test]
+    fn localized() {
+        let source = Source::new("main.lam", "x");
+        let mut catalog = Catalog::new();
+        catalog.add_resource("pt", "unused = `{ $name }` não usada").unwrap();
+        let diagnostic = Localized { span: source.span(0, 1) };
+        let mut renderer = Renderer::new();
+        renderer.set_catalog(Some(Arc::new(catalog))).set_locale("pt-BR");
+        let rendered = renderer.render(&diagnostic).to_string();
+        assert_eq!(
+            rendered,
+            "warning: `x` não usada\n --> main.lam:1:1\n  |\n1 | x\n  | ^ \
+             never read\n  = help: remove it\n"
+        );
+        renderer.set_locale("en");
+        let rendered = renderer.render(&diagnostic).to_string();
+        assert!(rendered.starts_with("warning: unused variable `x`\n"));
+    }
+}
@@ -0,0 +1,419 @@
+//! This module provides [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html)
+//! output of diagnostics, for consumption by code-scanning pipelines. Only
+//! available with the `sarif` feature.
+//!
+//! A whole [`Diagnostics`] collection becomes a single run of a log, and each
+//! diagnostic becomes a result:
+//!
+//! - [`Level::Note`], [`Level::Warning`] and [`Level::Error`] map to the
+//!   SARIF levels `note`, `warning` and `error`, while [`Level::Help`] maps
+//!   to `note` and [`Level::Bug`] to `error`.
+//! - [`Source::name`](crate::source::Source::name) is used as the artifact
+//!   location URI (percent-encoded where needed), so source names should be
+//!   paths relative to the root of the scanned repository.
+//! - Primary labels become the result's locations, while secondary labels and
+//!   children with spans become related locations.
+//! - Suggestions become fixes.
+//! - Codes become rule identifiers, and each distinct code is listed among
+//!   the rules of the tool.
+//!
+//! Regions use 1-based lines and columns, with columns counted in unicode
+//! scalar values (the run's `columnKind` is `unicodeCodePoints`), not in
+//! string segments. Byte offsets and lengths are also given.
+
+use super::{Diagnostic, Diagnostics, Level, Suggestion};
+use crate::source::Span;
+use serde_json::{json, Map, Value};
+use std::io;
+
+/// SARIF version emitted.
+pub const VERSION: &str = "2.1.0";
+
+/// URI of the SARIF schema emitted.
+pub const SCHEMA_URI: &str = "https://docs.oasis-open.org/sarif/sarif/v2.1.0/\
+                              errata01/os/schemas/sarif-schema-2.1.0.json";
+
+/// Information about the tool (e.g. the compiler) that produced the
+/// diagnostics.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Tool {
+    /// Name of the tool.
+    pub name: String,
+    /// Version of the tool, if known.
+    pub version: Option<String>,
+    /// Absolute URI where information about the tool can be found, if any.
+    pub information_uri: Option<String>,
+}
+
+impl Tool {
+    /// Creates tool information with the given name only.
+    pub fn new<S>(name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self { name: name.into(), version: None, information_uri: None }
+    }
+
+    /// Sets the version of the tool.
+    pub fn with_version<S>(mut self, version: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Sets the URI where information about the tool can be found.
+    pub fn with_information_uri<S>(mut self, uri: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.information_uri = Some(uri.into());
+        self
+    }
+
+    /// Converts this tool information into a SARIF `toolComponent`.
+    fn to_value(&self) -> Value {
+        let mut driver = Map::new();
+        driver.insert("name".into(), json!(self.name));
+        if let Some(version) = &self.version {
+            driver.insert("version".into(), json!(version));
+        }
+        if let Some(uri) = &self.information_uri {
+            driver.insert("informationUri".into(), json!(uri));
+        }
+        Value::Object(driver)
+    }
+}
+
+/// Converts a collection of diagnostics into a SARIF log with a single run,
+/// with the levels given by the collection's policy.
+pub fn to_log<D>(tool: &Tool, diagnostics: &Diagnostics<D>) -> Value
+where
+    D: Diagnostic + ?Sized,
+{
+    let mut rules = Vec::<&str>::new();
+    let mut results = Vec::new();
+    for (level, diagnostic) in diagnostics.iter_leveled() {
+        let mut result = to_result(diagnostic);
+        result["level"] = json!(sarif_level(level));
+        if let Some(code) = diagnostic.code() {
+            let index = match rules.iter().position(|rule| *rule == code) {
+                Some(index) => index,
+                None => {
+                    rules.push(code);
+                    rules.len() - 1
+                },
+            };
+            result["ruleIndex"] = json!(index);
+        }
+        results.push(result);
+    }
+    let mut driver = tool.to_value();
+    if !rules.is_empty() {
+        let rules = rules
+            .into_iter()
+            .map(|code| json!({ "id": code }))
+            .collect::<Vec<_>>();
+        driver["rules"] = Value::Array(rules);
+    }
+    json!({
+        "$schema": SCHEMA_URI,
+        "version": VERSION,
+        "runs": [{
+            "tool": { "driver": driver },
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }],
+    })
+}
+
+/// Writes a collection of diagnostics as a SARIF log with a single run.
+pub fn write<W, D>(
+    output: &mut W,
+    tool: &Tool,
+    diagnostics: &Diagnostics<D>,
+) -> io::Result<()>
+where
+    W: io::Write + ?Sized,
+    D: Diagnostic + ?Sized,
+{
+    serde_json::to_writer_pretty(&mut *output, &to_log(tool, diagnostics))?;
+    writeln!(output)
+}
+
+/// Converts a single diagnostic into a SARIF result.
+pub fn to_result<D>(diagnostic: &D) -> Value
+where
+    D: Diagnostic + ?Sized,
+{
+    let mut locations = Vec::new();
+    let mut related = Vec::new();
+    for label in diagnostic.labels() {
+        let message = label.message.as_deref();
+        if label.is_primary() {
+            locations.push(location(&label.span, message, None));
+        } else {
+            let id = related.len();
+            related.push(location(&label.span, message, Some(id)));
+        }
+    }
+    for child in diagnostic.children() {
+        if let Some(span) = &child.span {
+            let message = format!("{}: {}", child.kind, child.message);
+            let id = related.len();
+            related.push(location(span, Some(&message), Some(id)));
+        }
+    }
+    let fixes = diagnostic
+        .suggestions()
+        .filter(|suggestion| !suggestion.edits.is_empty())
+        .map(|suggestion| fix(&suggestion))
+        .collect::<Vec<_>>();
+
+    let mut result = Map::new();
+    if let Some(code) = diagnostic.code() {
+        result.insert("ruleId".into(), json!(code));
+    }
+    result.insert("level".into(), json!(sarif_level(diagnostic.level())));
+    result.insert("message".into(), json!({ "text": diagnostic.to_string() }));
+    result.insert("locations".into(), Value::Array(locations));
+    if !related.is_empty() {
+        result.insert("relatedLocations".into(), Value::Array(related));
+    }
+    if !fixes.is_empty() {
+        result.insert("fixes".into(), Value::Array(fixes));
+    }
+    Value::Object(result)
+}
+
+/// Maps a level into a SARIF level.
+fn sarif_level(level: Level) -> &'static str {
+    match level {
+        Level::Help | Level::Note => "note",
+        Level::Warning => "warning",
+        Level::Error | Level::Bug => "error",
+    }
+}
+
+/// Converts a span into a SARIF `location`, with an optional message and
+/// id.
+fn location(span: &Span, message: Option<&str>, id: Option<usize>) -> Value {
+    let mut location = Map::new();
+    if let Some(id) = id {
+        location.insert("id".into(), json!(id));
+    }
+    location.insert(
+        "physicalLocation".into(),
+        json!({
+            "artifactLocation": artifact_location(span),
+            "region": region(span),
+        }),
+    );
+    if let Some(message) = message {
+        location.insert("message".into(), json!({ "text": message }));
+    }
+    Value::Object(location)
+}
+
+/// Converts the source of a span into a SARIF `artifactLocation`.
+fn artifact_location(span: &Span) -> Value {
+    json!({ "uri": uri(span.source().name()) })
+}
+
+/// Converts a span into a SARIF `region`.
+fn region(span: &Span) -> Value {
+    let start = span.start();
+    let end = span.end();
+    json!({
+        "startLine": start.line() + 1,
+        "startColumn": start.char_column() + 1,
+        "endLine": end.line() + 1,
+        "endColumn": end.char_column() + 1,
+        "byteOffset": start.byte_offset(),
+        "byteLength": end.byte_offset() - start.byte_offset(),
+    })
+}
+
+/// Converts a suggestion into a SARIF `fix`, grouping edits by source.
+fn fix(suggestion: &Suggestion) -> Value {
+    let mut changes = Vec::<(Value, Vec<Value>)>::new();
+    for edit in &suggestion.edits {
+        let artifact = artifact_location(edit.span());
+        let replacement = json!({
+            "deletedRegion": region(edit.span()),
+            "insertedContent": { "text": edit.replacement() },
+        });
+        match changes.iter_mut().find(|(other, _)| *other == artifact) {
+            Some((_, replacements)) => replacements.push(replacement),
+            None => changes.push((artifact, vec![replacement])),
+        }
+    }
+    let changes = changes
+        .into_iter()
+        .map(|(artifact, replacements)| {
+            json!({
+                "artifactLocation": artifact,
+                "replacements": replacements,
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "description": { "text": suggestion.message },
+        "artifactChanges": changes,
+    })
+}
+
+/// Makes a URI reference out of a source name, percent-encoding characters
+/// not allowed in a path and turning backslashes into slashes.
+fn uri(name: &str) -> String {
+    let mut uri = String::with_capacity(name.len());
+    for byte in name.bytes() {
+        match byte {
+            b'\\' => uri.push('/'),
+            b'a' ..= b'z'
+            | b'A' ..= b'Z'
+            | b'0' ..= b'9'
+            | b'-'
+            | b'.'
+            | b'_'
+            | b'~'
+            | b'/' => uri.push(char::from(byte)),
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+#[cfg(test)]
+mod test {
+    use super::{to_log, Tool};
+    use crate::{
+        diagnostic::{
+            Applicability,
+            Child,
+            Diagnostic,
+            Diagnostics,
+            Label,
+            Level,
+            Suggestion,
+        },
+        source::{Edit, Source, Span},
+    };
+    use jsonschema::JSONSchema;
+    use serde_json::Value;
+    use std::fmt;
+
+    /// A subset of the official SARIF 2.1.0 schema, trimmed to the objects
+    /// emitted here. It should be replaced by the official file, unchanged,
+    /// as `tests/fixtures/sarif-schema-2.1.0.json`.
+    const SCHEMA: &str =
+        include_str!("../../tests/fixtures/sarif-schema-2.1.0-subset.json");
+
+    #[derive(Debug)]
+    struct Shadowed {
+        name: Span,
+        previous: Span,
+    }
+
+    impl fmt::Display for Shadowed {
+        fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmtr, "`{}` shadows a previous binding", self.name.as_str())
+        }
+    }
+
+    impl Diagnostic for Shadowed {
+        fn level(&self) -> Level {
+            Level::Warning
+        }
+
+        fn primary_span(&self) -> Option<Span> {
+            Some(self.name.clone())
+        }
+
+        fn code(&self) -> Option<&str> {
+            Some("W0001")
+        }
+
+        fn labels<'this>(
+            &'this self,
+        ) -> Box<dyn Iterator<Item = Label> + Send + Sync + 'this> {
+            let labels = vec![
+                Label::primary(self.name.clone()),
+                Label::secondary(self.previous.clone())
+                    .with_message("previous binding"),
+            ];
+            Box::new(labels.into_iter())
+        }
+
+        fn children<'this>(
+            &'this self,
+        ) -> Box<dyn Iterator<Item = Child> + Send + Sync + 'this> {
+            let note =
+                Child::note("bound here").with_span(self.previous.clone());
+            Box::new(Some(note).into_iter())
+        }
+
+        fn suggestions<'this>(
+            &'this self,
+        ) -> Box<dyn Iterator<Item = Suggestion> + Send + Sync + 'this>
+        {
+            let rename =
+                Suggestion::new("rename it", Applicability::MaybeIncorrect)
+                    .with_edit(Edit::replace(self.name.clone(), "y"));
+            Box::new(Some(rename).into_iter())
+        }
+    }
+
+    fn log() -> Value {
+        let source = Source::new("src/my lib.lam", "\\e\u{301}. \\e\u{301}. x");
+        let mut diagnostics: Diagnostics = Diagnostics::new();
+        diagnostics.raise(Shadowed {
+            name: source.span(5, 1),
+            previous: source.span(1, 1),
+        });
+        let tool = Tool::new("lambda").with_version("0.1.0");
+        to_log(&tool, &diagnostics)
+    }
+
+    #[test]
+    fn validates_against_schema() {
+        let schema = serde_json::from_str(SCHEMA).unwrap();
+        let compiled = JSONSchema::compile(&schema).unwrap();
+        let log = log();
+        let errors = match compiled.validate(&log) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors.map(|e| e.to_string()).collect(),
+        };
+        assert!(errors.is_empty(), "invalid SARIF: {:#?}", errors);
+    }
+
+    #[test]
+    fn result_contents() {
+        let log = log();
+        let run = &log["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "lambda");
+        assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "W0001");
+        let result = &run["results"][0];
+        assert_eq!(result["ruleId"], "W0001");
+        assert_eq!(result["ruleIndex"], 0);
+        assert_eq!(result["level"], "warning");
+        assert_eq!(
+            result["message"]["text"],
+            "`e\u{301}` shadows a previous binding"
+        );
+        let location = &result["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "src/my%20lib.lam");
+        assert_eq!(location["region"]["startColumn"], 7);
+        assert_eq!(location["region"]["endColumn"], 9);
+        assert_eq!(location["region"]["byteOffset"], 7);
+        assert_eq!(location["region"]["byteLength"], 3);
+        let related = result["relatedLocations"].as_array().unwrap();
+        assert_eq!(related.len(), 2);
+        assert_eq!(related[0]["message"]["text"], "previous binding");
+        assert_eq!(related[1]["message"]["text"], "note: bound here");
+        let fix = &result["fixes"][0];
+        let replacement = &fix["artifactChanges"][0]["replacements"][0];
+        assert_eq!(replacement["insertedContent"]["text"], "y");
+    }
+}
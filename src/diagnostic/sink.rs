@@ -0,0 +1,215 @@
+//! This module provides a diagnostics sink shared between threads, e.g. when
+//! lexing and parsing many files in parallel.
+
+use super::{Diagnostic, Diagnostics, Level, Policy};
+use std::{
+    mem,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+/// A cloneable handle to a [`Diagnostics`] collection shared between threads.
+/// Every clone refers to the same collection.
+///
+/// Diagnostics can be raised directly into the sink, or collected per thread
+/// and [merged](Sink::merge) in. Since threads raise diagnostics in no
+/// particular order, [`finish`](Sink::finish) sorts them by location.
+#[derive(Debug)]
+pub struct Sink<D = dyn Diagnostic + Send + Sync>
+where
+    D: Diagnostic + ?Sized,
+{
+    /// The shared collection.
+    inner: Arc<Mutex<Diagnostics<D>>>,
+}
+
+impl<D> Clone for Sink<D>
+where
+    D: Diagnostic + ?Sized,
+{
+    fn clone(&self) -> Self {
+        Self { inner: self.inner.clone() }
+    }
+}
+
+impl<D> Default for Sink<D>
+where
+    D: Diagnostic + ?Sized,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<D> From<Diagnostics<D>> for Sink<D>
+where
+    D: Diagnostic + ?Sized,
+{
+    fn from(diagnostics: Diagnostics<D>) -> Self {
+        Self { inner: Arc::new(Mutex::new(diagnostics)) }
+    }
+}
+
+impl<D> Sink<D>
+where
+    D: Diagnostic + ?Sized,
+{
+    /// Creates a sink with an empty collection of diagnostics.
+    pub fn new() -> Self {
+        Self::from(Diagnostics::new())
+    }
+
+    /// Creates a sink with an empty collection of diagnostics and the given
+    /// policy.
+    pub fn with_policy(policy: Policy) -> Self {
+        Self::from(Diagnostics::with_policy(policy))
+    }
+
+    /// Locks the shared collection for direct access, blocking until no other
+    /// thread holds it.
+    pub fn lock(&self) -> MutexGuard<'_, Diagnostics<D>> {
+        // A panicking thread cannot leave the collection in an invalid state.
+        self.inner.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    /// Raises a new diagnostic into the shared collection. See
+    /// [`Diagnostics::raise`].
+    pub fn raise<T>(&self, diagnostic: T)
+    where
+        Box<T>: Into<Box<D>>,
+    {
+        self.lock().raise(diagnostic);
+    }
+
+    /// Moves the diagnostics of the given collection into the shared one. See
+    /// [`Diagnostics::merge`].
+    pub fn merge(&self, diagnostics: Diagnostics<D>) {
+        self.lock().merge(diagnostics);
+    }
+
+    /// Returns whether the source code status is OK (no hard errors) according
+    /// to the shared collection.
+    pub fn is_ok(&self) -> bool {
+        self.lock().is_ok()
+    }
+
+    /// Returns whether the source code status is NOT OK (there are hard errors)
+    /// according to the shared collection.
+    pub fn is_err(&self) -> bool {
+        self.lock().is_err()
+    }
+
+    /// Returns the maximum effective level among the diagnostics in the shared
+    /// collection, including omitted errors.
+    pub fn max_level(&self) -> Option<Level> {
+        self.lock().max_level()
+    }
+
+    /// Returns whether the error limit of the shared collection has been
+    /// reached.
+    pub fn is_exhausted(&self) -> bool {
+        self.lock().is_exhausted()
+    }
+
+    /// Number of hard errors raised into the shared collection, both stored
+    /// and omitted.
+    pub fn error_count(&self) -> usize {
+        self.lock().error_count()
+    }
+
+    /// Takes the diagnostics out of the shared collection, sorted by location
+    /// (see [`Diagnostics::sort`]). Other handles to the sink are left with an
+    /// empty collection with the same policy and error limit.
+    pub fn finish(self) -> Diagnostics<D> {
+        let mut shared = self.lock();
+        let empty = shared.empty_like();
+        let mut diagnostics = mem::replace(&mut *shared, empty);
+        diagnostics.sort();
+        diagnostics
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Sink;
+    use crate::{
+        diagnostic::{Diagnostic, Diagnostics, Level},
+        source::{Source, Span},
+    };
+    use std::{fmt, thread};
+
+    #[derive(Debug)]
+    struct Unexpected {
+        span: Span,
+    }
+
+    impl fmt::Display for Unexpected {
+        fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmtr, "unexpected {}", self.span.as_str())
+        }
+    }
+
+    impl Diagnostic for Unexpected {
+        fn level(&self) -> Level {
+            Level::Error
+        }
+
+        fn primary_span(&self) -> Option<Span> {
+            Some(self.span.clone())
+        }
+    }
+
+    fn unexpected(source: &Source) -> Unexpected {
+        let mut reader = source.reader();
+        reader.mark();
+        reader.next();
+        Unexpected { span: reader.span() }
+    }
+
+    #[test]
+    fn parallel() {
+        let sources = (0 .. 8)
+            .map(|i| Source::new(format!("{}.lam", i), i.to_string()))
+            .collect::<Vec<_>>();
+        let sink: Sink = Sink::new();
+        thread::scope(|scope| {
+            for (i, source) in sources.iter().enumerate() {
+                let sink = sink.clone();
+                scope.spawn(move || {
+                    if i % 2 == 0 {
+                        sink.raise(unexpected(source));
+                    } else {
+                        let mut diagnostics = Diagnostics::new();
+                        diagnostics.raise(unexpected(source));
+                        sink.merge(diagnostics);
+                    }
+                });
+            }
+        });
+        assert_eq!(sink.error_count(), 8);
+        assert!(sink.is_err());
+        let messages = sink
+            .finish()
+            .iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect::<Vec<_>>();
+        let expected =
+            (0 .. 8).map(|i| format!("unexpected {}", i)).collect::<Vec<_>>();
+        assert_eq!(messages, expected);
+    }
+
+    #[test]
+    fn merge_error_limit() {
+        let source = Source::new("main.lam", "x");
+        let mut diagnostics: Diagnostics = Diagnostics::new();
+        diagnostics.set_error_limit(Some(2));
+        let mut other = Diagnostics::new();
+        for _ in 0 .. 3 {
+            other.raise(unexpected(&source));
+        }
+        let sink = Sink::from(diagnostics);
+        sink.merge(other);
+        let diagnostics = sink.finish();
+        assert_eq!(diagnostics.iter().count(), 2);
+        assert_eq!(diagnostics.omitted_errors(), 1);
+    }
+}
@@ -0,0 +1,61 @@
+//! This module provides suggestions: edits on the source code proposed by a
+//! diagnostic in order to fix the problem it reports.
+
+use crate::source::{Edit, RewriteError, Rewriter};
+
+/// How confident a [`Suggestion`] is of being correct, and thus whether it
+/// can be applied automatically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Applicability {
+    /// The suggestion is definitely what the user intended, and it can be
+    /// applied automatically.
+    MachineApplicable,
+    /// The suggestion might be what the user intended, but it might also
+    /// change the meaning of the code.
+    MaybeIncorrect,
+    /// The suggestion contains placeholders that must be filled by the user,
+    /// e.g. `<type>`.
+    HasPlaceholders,
+}
+
+impl Applicability {
+    /// Returns whether the suggestion can be applied automatically.
+    pub fn is_machine_applicable(self) -> bool {
+        self == Applicability::MachineApplicable
+    }
+}
+
+/// A suggested fix: a set of edits to be applied together, described by a
+/// message such as "consider adding `.`".
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Suggestion {
+    /// Message describing the suggestion.
+    pub message: String,
+    /// Edits to be applied together.
+    pub edits: Vec<Edit>,
+    /// How confident the suggestion is of being correct.
+    pub applicability: Applicability,
+}
+
+impl Suggestion {
+    /// Creates a suggestion with the given message and applicability, but no
+    /// edits.
+    pub fn new<S>(message: S, applicability: Applicability) -> Self
+    where
+        S: Into<String>,
+    {
+        Self { message: message.into(), edits: Vec::new(), applicability }
+    }
+
+    /// Adds an edit to this suggestion.
+    pub fn with_edit(mut self, edit: Edit) -> Self {
+        self.edits.push(edit);
+        self
+    }
+
+    /// Adds all edits of this suggestion to the given rewriter, or none of
+    /// them if any one fails to be added.
+    pub fn apply(&self, rewriter: &mut Rewriter) -> Result<(), RewriteError> {
+        rewriter.add_all(self.edits.iter().cloned())
+    }
+}
@@ -0,0 +1,330 @@
+//! This module provides inline suppression of diagnostics through directives
+//! written in comments of the source code, such as
+//! `// bittongue-allow: unused-var`.
+
+use super::{Diagnostic, Diagnostics, Level, Policy};
+use crate::source::{Source, Span};
+use std::{fmt, ops::Range, slice};
+
+/// A suppression directive found in a source code.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Suppression {
+    /// Span of the line containing the directive.
+    pub span: Span,
+    /// Codes of the diagnostics suppressed.
+    pub codes: Vec<String>,
+    /// Range of suppressed lines, starting from `0`. Includes the line of the
+    /// directive itself.
+    pub lines: Range<usize>,
+}
+
+impl Suppression {
+    /// Returns whether the given diagnostic is suppressed by this directive
+    /// under the given policy, i.e. it has one of the directive's codes and
+    /// its primary span starts in one of the suppressed lines. Hard errors
+    /// are never suppressed, and neither are lints the policy forbids.
+    /// Lints the policy merely denies, and warnings it promotes to errors,
+    /// can still be suppressed.
+    pub fn suppresses<D>(&self, policy: &Policy, diagnostic: &D) -> bool
+    where
+        D: Diagnostic + ?Sized,
+    {
+        self.matching_code(policy, diagnostic).is_some()
+    }
+
+    /// Finds the index of the code of this directive suppressing the given
+    /// diagnostic under the given policy, if any.
+    fn matching_code<D>(
+        &self,
+        policy: &Policy,
+        diagnostic: &D,
+    ) -> Option<usize>
+    where
+        D: Diagnostic + ?Sized,
+    {
+        if diagnostic.level() >= Level::Error || policy.forbids(diagnostic) {
+            return None;
+        }
+        let span = diagnostic.primary_span()?;
+        if span.source() != self.span.source()
+            || !self.lines.contains(&span.start().line())
+        {
+            return None;
+        }
+        let code = diagnostic.code()?;
+        self.codes.iter().position(|suppressed| suppressed == code)
+    }
+}
+
+/// A set of suppression directives, collected from one or more sources.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Suppressions {
+    /// Directives collected, in order.
+    entries: Vec<Suppression>,
+}
+
+impl Suppressions {
+    /// Creates an empty set of suppression directives.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collects the suppression directives of the given source. The
+    /// `recognize` callback is called with the text of each line (without
+    /// the line break), and yields the codes suppressed by the line's
+    /// directive together with whether the directive stands alone on its
+    /// line, or `None` if the line has no directive.
+    ///
+    /// A directive written after code suppresses diagnostics on its own line
+    /// only. A directive standing alone suppresses diagnostics on its own
+    /// line and on the next line without a standalone directive, so such
+    /// directives can be stacked above code.
+    pub fn collect<F, I>(
+        &mut self,
+        source: &Source,
+        mut recognize: F,
+    ) -> &mut Self
+    where
+        F: FnMut(&str) -> Option<(I, bool)>,
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let line_count = source.line(source.len()) + 1;
+        let mut reader = source.reader();
+        let mut pending = Vec::<usize>::new();
+        for line in 0 .. line_count {
+            let start = source.line_start(line);
+            let end = source.line_end(line);
+            for &index in &pending {
+                self.entries[index].lines.end = line + 1;
+            }
+            match recognize(&source[start .. end]) {
+                Some((codes, standalone)) => {
+                    reader.advance(start - reader.position());
+                    reader.mark();
+                    reader.advance(end - start);
+                    if standalone {
+                        pending.push(self.entries.len());
+                    } else {
+                        pending.clear();
+                    }
+                    self.entries.push(Suppression {
+                        span: reader.span(),
+                        codes: codes.into_iter().map(Into::into).collect(),
+                        lines: line .. line + 1,
+                    });
+                },
+                None => pending.clear(),
+            }
+        }
+        self
+    }
+
+    /// Creates an iterator over the directives collected.
+    pub fn iter(&self) -> slice::Iter<'_, Suppression> {
+        self.entries.iter()
+    }
+
+    /// Drops the diagnostics of the given collection suppressed by some
+    /// directive under the collection's policy, and raises an
+    /// [`UnusedSuppression`] warning for each code of a directive that
+    /// suppressed nothing.
+    pub fn apply<D>(&self, diagnostics: &mut Diagnostics<D>)
+    where
+        D: Diagnostic + ?Sized,
+        Box<UnusedSuppression>: Into<Box<D>>,
+    {
+        let mut used = self
+            .entries
+            .iter()
+            .map(|entry| vec![false; entry.codes.len()])
+            .collect::<Vec<_>>();
+        let policy = diagnostics.policy().clone();
+        diagnostics.retain(|diagnostic| {
+            let mut kept = true;
+            for (entry, used) in self.entries.iter().zip(&mut used) {
+                if let Some(index) = entry.matching_code(&policy, diagnostic) {
+                    used[index] = true;
+                    kept = false;
+                }
+            }
+            kept
+        });
+        for (entry, used) in self.entries.iter().zip(used) {
+            for (code, used) in entry.codes.iter().zip(used) {
+                if !used {
+                    diagnostics.raise(UnusedSuppression {
+                        span: entry.span.clone(),
+                        code: code.clone(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl<'suppressions> IntoIterator for &'suppressions Suppressions {
+    type Item = &'suppressions Suppression;
+    type IntoIter = slice::Iter<'suppressions, Suppression>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Warning raised for a code of a suppression directive that did not
+/// suppress any diagnostic. Its code is `unused-suppression`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UnusedSuppression {
+    /// Span of the line containing the directive.
+    pub span: Span,
+    /// Code that was not used.
+    pub code: String,
+}
+
+impl fmt::Display for UnusedSuppression {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmtr, "unused suppression of `{}`", self.code)
+    }
+}
+
+impl Diagnostic for UnusedSuppression {
+    fn level(&self) -> Level {
+        Level::Warning
+    }
+
+    fn primary_span(&self) -> Option<Span> {
+        Some(self.span.clone())
+    }
+
+    fn code(&self) -> Option<&str> {
+        Some("unused-suppression")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Suppressions;
+    use crate::{
+        diagnostic::{Diagnostic, Diagnostics, Level, LintLevel, Policy},
+        source::{Source, Span},
+    };
+    use std::fmt;
+
+    #[derive(Debug)]
+    struct Lint {
+        level: Level,
+        code: &'static str,
+        span: Span,
+    }
+
+    impl fmt::Display for Lint {
+        fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmtr, "{} at {}", self.code, self.span.as_str())
+        }
+    }
+
+    impl Diagnostic for Lint {
+        fn level(&self) -> Level {
+            self.level
+        }
+
+        fn primary_span(&self) -> Option<Span> {
+            Some(self.span.clone())
+        }
+
+        fn code(&self) -> Option<&str> {
+            Some(self.code)
+        }
+    }
+
+    fn recognize(line: &str) -> Option<(Vec<String>, bool)> {
+        let (code, directive) = line.split_once("# allow: ")?;
+        let codes = directive.split(", ").map(str::to_owned).collect();
+        Some((codes, code.trim().is_empty()))
+    }
+
+    #[test]
+    fn collect() {
+        let source = Source::new(
+            "main.txt",
+            "a\n# allow: x\n# allow: y, z\nb\nc # allow: w\nd\n# allow: v\ne # \
+             allow: u\nf",
+        );
+        let mut suppressions = Suppressions::new();
+        suppressions.collect(&source, recognize);
+        let collected = suppressions
+            .iter()
+            .map(|entry| (entry.span.as_str(), &entry.codes, &entry.lines))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            collected,
+            [
+                ("# allow: x", &vec!["x".to_owned()], &(1 .. 4)),
+                (
+                    "# allow: y, z",
+                    &vec!["y".to_owned(), "z".to_owned()],
+                    &(2 .. 4)
+                ),
+                ("c # allow: w", &vec!["w".to_owned()], &(4 .. 5)),
+                ("# allow: v", &vec!["v".to_owned()], &(6 .. 8)),
+                ("e # allow: u", &vec!["u".to_owned()], &(7 .. 8)),
+            ]
+        );
+    }
+
+    #[test]
+    fn apply() {
+        let source = Source::new("main.txt", "# allow: x, y\nab\nc");
+        let mut suppressions = Suppressions::new();
+        suppressions.collect(&source, recognize);
+        let mut diagnostics: Diagnostics = Diagnostics::new();
+        let lints = [
+            (Level::Warning, "x", 14),
+            (Level::Error, "x", 15),
+            (Level::Warning, "x", 17),
+        ];
+        for &(level, code, start) in &lints {
+            diagnostics.raise(Lint {
+                level,
+                code,
+                span: source.span(start, 1),
+            });
+        }
+        suppressions.apply(&mut diagnostics);
+        let messages = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(messages, ["x at b", "x at c", "unused suppression of `y`"]);
+    }
+
+    #[test]
+    fn apply_effective_levels() {
+        let source = Source::new("main.txt", "# allow: x, y, z\nabc");
+        let mut suppressions = Suppressions::new();
+        suppressions.collect(&source, recognize);
+        let mut policy = Policy::new();
+        policy
+            .set_code("x", LintLevel::Forbid)
+            .set_code("y", LintLevel::Deny)
+            .set_deny_warnings(true);
+        let mut diagnostics: Diagnostics = Diagnostics::with_policy(policy);
+        for (code, start) in [("x", 17), ("y", 18), ("z", 19)] {
+            diagnostics.raise(Lint {
+                level: Level::Warning,
+                code,
+                span: source.span(start, 1),
+            });
+        }
+        suppressions.apply(&mut diagnostics);
+        let messages = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            ["x at a", "unused suppression of `x`"]
+        );
+    }
+}
@@ -137,13 +137,13 @@ impl GraphemeCluster {
     /// Returns whether this grapheme cluster is alphabetic (and only if no
     /// diacritics are present).
     pub fn is_alphabetic_char(&self) -> bool {
-        self.to_char().map_or(false, char::is_alphabetic)
+        self.to_char().is_some_and(char::is_alphabetic)
     }
 
     /// Returns whether this grapheme cluster is a single ASCII alphabetic
     /// character.
     pub fn is_ascii_alphabetic(&self) -> bool {
-        self.to_char().map_or(false, |ch| ch.is_ascii_alphabetic())
+        self.to_char().is_some_and(|ch| ch.is_ascii_alphabetic())
     }
 
     /// Returns whether this grapheme cluster is numeric (possibly with
@@ -155,13 +155,13 @@ impl GraphemeCluster {
     /// Returns whether this grapheme cluster is numeric (and only if no
     /// diacritics are present).
     pub fn is_numeric_char(&self) -> bool {
-        self.to_char().map_or(false, char::is_numeric)
+        self.to_char().is_some_and(char::is_numeric)
     }
 
     /// Returns whether this grapheme cluster is a single ASCII numeric
     /// character.
     pub fn is_ascii_numeric(&self) -> bool {
-        self.to_char().map_or(false, |ch| ch.is_ascii_digit())
+        self.to_char().is_some_and(|ch| ch.is_ascii_digit())
     }
 
     /// Returns whether this grapheme cluster is alphabetic or numeric (possibly
@@ -173,13 +173,13 @@ impl GraphemeCluster {
     /// Returns whether this grapheme cluster is alphabetic or numeric (and only
     /// if no diacritics are present).
     pub fn is_alphanumeric_char(&self) -> bool {
-        self.to_char().map_or(false, char::is_alphanumeric)
+        self.to_char().is_some_and(char::is_alphanumeric)
     }
 
     /// Returns whether this grapheme cluster is a single ASCII alphabetic or
     /// numeric character.
     pub fn is_ascii_alphanumeric(&self) -> bool {
-        self.to_char().map_or(false, |ch| ch.is_ascii_alphanumeric())
+        self.to_char().is_some_and(|ch| ch.is_ascii_alphanumeric())
     }
 
     /// Returns whether this grapheme cluster is a digit of the given base.
@@ -193,7 +193,7 @@ impl GraphemeCluster {
     /// Only base 2 to 36 are allowed, using ASCII alphanumeric characters.
     /// Diacritics are not allowed and yield `false`.
     pub fn is_digit_char(&self, base: u32) -> bool {
-        self.to_char().map_or(false, |ch| ch.is_digit(base))
+        self.to_char().is_some_and(|ch| ch.is_digit(base))
     }
 
     /// Attempts to convert this grapheme cluster into a digit of the given
@@ -219,7 +219,7 @@ impl GraphemeCluster {
     /// Returns whether this grapheme cluster is whitespace (and only if no
     /// diacritics are present).
     pub fn is_whitespace_char(&self) -> bool {
-        self.to_char().map_or(false, char::is_whitespace)
+        self.to_char().is_some_and(char::is_whitespace)
     }
 }
 
@@ -230,13 +230,13 @@ impl fmt::Display for GraphemeCluster {
 }
 
 /// Creates an iterator over grapheme clusters of a string.
-pub fn iter(input: &str) -> Iter {
+pub fn iter(input: &str) -> Iter<'_> {
     Iter { inner: input.graphemes(true) }
 }
 
 /// Creates an indexed iterator over grapheme clusters of a string, yielding
 /// grapheme indices in terms of bytes together with the graphemes.
-pub fn indexed_iter(input: &str) -> IndexedIter {
+pub fn indexed_iter(input: &str) -> IndexedIter<'_> {
     IndexedIter { inner: input.grapheme_indices(true) }
 }
 
@@ -67,6 +67,10 @@ pub trait Lexer {
     /// reading `Result<Token<K>, LexingError>` than `Option<Token<K>>`). Note
     /// that it is expected that when [`LexingError`] is returned, at least
     /// one error is placed on [`Diagnostics`].
+    ///
+    /// Lexers that may raise many errors in a row should check
+    /// [`Diagnostics::is_exhausted`], and skip to the end of input once the
+    /// error limit is reached.
     fn generate_token(
         &mut self,
         reader: &mut Reader,
@@ -74,7 +78,7 @@ pub trait Lexer {
     ) -> Result<Token<Self::TokenKind>, LexingError>;
 }
 
-impl<'this, L> Lexer for &'this mut L
+impl<L> Lexer for &mut L
 where
     L: Lexer + ?Sized,
 {
@@ -106,6 +110,10 @@ where
 
 /// A stream of [`Token`]s, where tokens are generated lazily, but everything is
 /// saved. Uses a generic [`Lexer`] to generate tokens.
+///
+/// Once the error limit of the [`Diagnostics`] is reached, the stream stops
+/// lexing early: it skips to the end of input, so the lexer produces the EOF
+/// token next.
 #[derive(Debug, Clone)]
 pub struct TokenStream<L>
 where
@@ -115,6 +123,7 @@ where
     lexer: L,
     tokens: Vec<Result<Token<L::TokenKind>, LexingError>>,
     position: usize,
+    aborted: bool,
 }
 
 impl<L> TokenStream<L>
@@ -133,16 +142,22 @@ where
             lexer,
             tokens: Vec::new(),
             position: 0,
+            aborted: false,
         };
-        let tok_res = this.lexer.generate_token(&mut this.reader, diagnostics);
-        this.tokens.push(tok_res);
+        this.generate_token(diagnostics);
         this
     }
 
     /// Returns whether the End-Of-File has been reached. More specifically,
     /// tests if current token is an EOF token.
     pub fn is_eof(&self) -> bool {
-        self.current().map_or(false, |token| token.kind.is_eof())
+        self.current().is_ok_and(|token| token.kind.is_eof())
+    }
+
+    /// Returns whether the stream stopped early because the error limit of the
+    /// [`Diagnostics`] was reached, skipping the rest of the input.
+    pub fn is_aborted(&self) -> bool {
+        self.aborted
     }
 
     /// Returns the position of the current token.
@@ -172,9 +187,7 @@ where
         } else {
             self.position += 1;
             if self.position >= self.tokens.len() {
-                let tok_res =
-                    self.lexer.generate_token(&mut self.reader, diagnostics);
-                self.tokens.push(tok_res);
+                self.generate_token(diagnostics);
             }
             true
         }
@@ -209,6 +222,18 @@ where
         advanced
     }
 
+    /// Generates a new token and saves it, skipping to the end of input first
+    /// if the error limit has been reached.
+    fn generate_token(&mut self, diagnostics: &mut Diagnostics) {
+        if diagnostics.is_exhausted() && !self.reader.is_eof() {
+            self.aborted = true;
+            let remaining = self.source().len() - self.reader.position();
+            self.reader.advance(remaining);
+        }
+        let tok_res = self.lexer.generate_token(&mut self.reader, diagnostics);
+        self.tokens.push(tok_res);
+    }
+
     /// Rollsback stream position by `count` positions, and returns how many
     /// positions were actually back. Note that it will stop if position `0`
     /// will be reached.
@@ -218,3 +243,87 @@ where
         rolled
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{Lexer, LexingError, Token, TokenKind, TokenStream};
+    use crate::{
+        diagnostic::{Diagnostic, Diagnostics, Level},
+        source::{Reader, Source, Span},
+    };
+    use std::fmt;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Kind {
+        Letter,
+        Eof,
+    }
+
+    impl TokenKind for Kind {
+        fn is_eof(&self) -> bool {
+            *self == Kind::Eof
+        }
+    }
+
+    #[derive(Debug)]
+    struct Invalid {
+        span: Span,
+    }
+
+    impl fmt::Display for Invalid {
+        fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmtr, "invalid {}", self.span.as_str())
+        }
+    }
+
+    impl Diagnostic for Invalid {
+        fn level(&self) -> Level {
+            Level::Error
+        }
+
+        fn primary_span(&self) -> Option<Span> {
+            Some(self.span.clone())
+        }
+    }
+
+    struct Letters;
+
+    impl Lexer for Letters {
+        type TokenKind = Kind;
+
+        fn generate_token(
+            &mut self,
+            reader: &mut Reader,
+            diagnostics: &mut Diagnostics,
+        ) -> Result<Token<Kind>, LexingError> {
+            reader.mark();
+            if reader.is_eof() {
+                return Ok(Token { kind: Kind::Eof, span: reader.span() });
+            }
+            let is_letter = reader.test(|grapheme| grapheme == "a");
+            reader.next();
+            if is_letter {
+                Ok(Token { kind: Kind::Letter, span: reader.span() })
+            } else {
+                diagnostics.raise(Invalid { span: reader.span() });
+                Err(LexingError)
+            }
+        }
+    }
+
+    #[test]
+    fn abort_on_error_limit() {
+        let source = Source::new("main.txt", "a??a??a");
+        let mut diagnostics: Diagnostics = Diagnostics::new();
+        diagnostics.set_error_limit(Some(2));
+        let mut stream = TokenStream::new(&source, Letters, &mut diagnostics);
+        let mut tokens = 1;
+        while stream.next(&mut diagnostics) {
+            tokens += 1;
+        }
+        assert!(stream.is_eof());
+        assert!(stream.is_aborted());
+        assert_eq!(tokens, 4);
+        assert_eq!(diagnostics.error_count(), 2);
+    }
+}
@@ -326,3 +326,5 @@ pub mod grapheme;
 pub mod diagnostic;
 pub mod source;
 pub mod lexer;
+#[cfg(any(feature = "testing", test))]
+pub mod testing;
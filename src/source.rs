@@ -41,24 +41,35 @@
 //! # }
 //! ```
 
+mod edit;
 mod indexing;
+mod load;
 mod location;
+mod map;
 mod reader;
+mod revision;
+mod segmentation;
 mod span;
 
+pub use edit::{Edit, RewriteError, Rewriter};
 pub use indexing::SourceIndex;
 use indexing::{IndexArray, IndexArrayBuilder, IndexArrayIter};
+pub use load::{Encoding, LoadError, LoadErrorKind, Loader};
 pub use location::Location;
+pub use map::{CompactSpan, SourceMap, SourceMapFull, SourceMapIter};
 pub use reader::Reader;
+pub use revision::Revision;
+pub use segmentation::Segmentation;
 pub use span::{Span, SpanContent};
 use std::{
     cmp::Ordering,
     fmt,
     hash::{Hash, Hasher},
+    io,
     ops::Index,
+    path::Path,
     sync::Arc,
 };
-use unicode_segmentation::UnicodeSegmentation;
 
 /// Inner structure of a source.
 #[derive(Debug)]
@@ -71,6 +82,12 @@ struct SourceInner {
     segments: IndexArray,
     /// List of newlines in the source.
     newlines: IndexArray,
+    /// Unit of the string segments.
+    segmentation: Segmentation,
+    /// Encoding the contents were decoded from.
+    encoding: Encoding,
+    /// Whether the original contents started with a byte order mark.
+    bom: bool,
 }
 
 /// A source code object, such as read from a file. Cloning this object results
@@ -85,8 +102,40 @@ pub struct Source {
 impl Source {
     /// Creates a new source code object given its name and its contents.
     ///
-    /// Contents are rearranged as grapheme clusters.
+    /// Contents are rearranged as extended grapheme clusters. See
+    /// [`Source::with_segmentation`] for other units.
     pub fn new<S0, S1>(name: S0, contents: S1) -> Self
+    where
+        S0: Into<Box<str>>,
+        S1: Into<Box<str>>,
+    {
+        Self::with_segmentation(name, contents, Segmentation::default())
+    }
+
+    /// Creates a new source code object given its name, its contents and
+    /// the unit its contents are segmented into.
+    pub fn with_segmentation<S0, S1>(
+        name: S0,
+        contents: S1,
+        segmentation: Segmentation,
+    ) -> Self
+    where
+        S0: Into<Box<str>>,
+        S1: Into<Box<str>>,
+    {
+        Self::from_parts(name, contents, segmentation, Encoding::Utf8, false)
+    }
+
+    /// Creates a new source code object given its name, its contents, the
+    /// unit they are segmented into, the encoding they were decoded from,
+    /// and whether they had a byte order mark.
+    pub(super) fn from_parts<S0, S1>(
+        name: S0,
+        contents: S1,
+        segmentation: Segmentation,
+        encoding: Encoding,
+        bom: bool,
+    ) -> Self
     where
         S0: Into<Box<str>>,
         S1: Into<Box<str>>,
@@ -96,8 +145,8 @@ impl Source {
         let mut segments = IndexArrayBuilder::new();
         let mut newlines = IndexArrayBuilder::new();
 
-        for (idx, grapheme) in contents.grapheme_indices(true) {
-            if grapheme == "\n" {
+        for (idx, is_newline) in segmentation.boundaries(&contents) {
+            if is_newline {
                 newlines.push(segments.len());
             }
             segments.push(idx);
@@ -106,10 +155,38 @@ impl Source {
 
         let segments = segments.into();
         let newlines = newlines.into();
-        let inner = SourceInner { name, contents, segments, newlines };
+        let inner = SourceInner {
+            name,
+            contents,
+            segments,
+            newlines,
+            segmentation,
+            encoding,
+            bom,
+        };
         Self { inner: Arc::new(inner) }
     }
 
+    /// Loads a source code object from the file at the given path, using the
+    /// path as its name. The encoding is detected as by [`Loader`].
+    pub fn from_path<P>(path: P) -> Result<Self, LoadError>
+    where
+        P: AsRef<Path>,
+    {
+        Loader::new().load_path(path)
+    }
+
+    /// Loads a source code object with the given name from the whole
+    /// contents of a reader, e.g. the standard input. The encoding is
+    /// detected as by [`Loader`].
+    pub fn from_reader<S, R>(name: S, reader: R) -> Result<Self, LoadError>
+    where
+        S: Into<String>,
+        R: io::Read,
+    {
+        Loader::new().load_reader(name, reader)
+    }
+
     /// The (file) name of the source.
     pub fn name(&self) -> &str {
         &self.inner.name
@@ -120,25 +197,47 @@ impl Source {
         self.inner.segments.len() - 1
     }
 
+    /// Returns whether the source is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// The contents of the source.
     pub fn contents(&self) -> &str {
         &self.inner.contents
     }
 
+    /// Unit the contents of the source are segmented into.
+    pub fn segmentation(&self) -> Segmentation {
+        self.inner.segmentation
+    }
+
+    /// Encoding the contents were decoded from when loaded, UTF-8 for sources
+    /// created in memory.
+    pub fn encoding(&self) -> Encoding {
+        self.inner.encoding
+    }
+
+    /// Whether the original contents started with a byte order mark, which
+    /// was stripped.
+    pub fn has_bom(&self) -> bool {
+        self.inner.bom
+    }
+
     /// Iterator over the segment indices of the source, in terms of bytes.
-    pub fn segments(&self) -> SegmentIndices {
+    pub fn segments(&self) -> SegmentIndices<'_> {
         SegmentIndices { inner: self.inner.segments.iter() }
     }
 
     /// Iterator over the newline indices of the source, in terms of grapheme
     /// clusters.
-    pub fn newlines(&self) -> NewlineIndices {
+    pub fn newlines(&self) -> NewlineIndices<'_> {
         NewlineIndices { inner: self.inner.segments.iter() }
     }
 
     /// Returns the line number where the given position is contained, starting
     /// from `0`.
-    fn line(&self, position: usize) -> usize {
+    pub(crate) fn line(&self, position: usize) -> usize {
         match self.inner.newlines.binary_search(position) {
             Ok(n) | Err(n) => n,
         }
@@ -149,7 +248,7 @@ impl Source {
     ///
     /// # Panics
     /// Pancis if the given line does not exist.
-    fn line_start(&self, line: usize) -> usize {
+    pub(crate) fn line_start(&self, line: usize) -> usize {
         if line == 0 {
             0
         } else {
@@ -159,7 +258,7 @@ impl Source {
 
     /// Returns the position of the given line number's start. Line number
     /// begins at `0`, returning `None` on invalid line number.
-    fn try_line_start(&self, line: usize) -> Option<usize> {
+    pub(crate) fn try_line_start(&self, line: usize) -> Option<usize> {
         if line == 0 {
             Some(0)
         } else {
@@ -167,6 +266,35 @@ impl Source {
         }
     }
 
+    /// Returns the position of the given line number's end, i.e. the position
+    /// of the newline finishing the line, or the length of the source if this
+    /// is the last line. Line number begins at `0`.
+    pub(crate) fn line_end(&self, line: usize) -> usize {
+        self.inner.newlines.get(line).unwrap_or(self.len())
+    }
+
+    /// Returns the byte offset of the given position, moved back to the start
+    /// of the character it splits, if any. Only positions of
+    /// [`Segmentation::Bytes`] can split a character.
+    pub(crate) fn floor_char_offset(&self, position: usize) -> usize {
+        let mut offset = self.inner.segments.index(position);
+        while !self.contents().is_char_boundary(offset) {
+            offset -= 1;
+        }
+        offset
+    }
+
+    /// Returns the byte offset of the given position, moved forward to the
+    /// end of the character it splits, if any. Only positions of
+    /// [`Segmentation::Bytes`] can split a character.
+    pub(crate) fn ceil_char_offset(&self, position: usize) -> usize {
+        let mut offset = self.inner.segments.index(position);
+        while !self.contents().is_char_boundary(offset) {
+            offset += 1;
+        }
+        offset
+    }
+
     /// Indexes this source. It can be a single `usize` or a range of `usize`.
     pub fn get<I>(&self, indexer: I) -> Option<&I::Output>
     where
@@ -179,6 +307,36 @@ impl Source {
     pub fn reader(&self) -> Reader {
         Reader::new(self.clone())
     }
+
+    /// Creates a span of the given length starting at the given position,
+    /// both in string segments, as a reader would: both are clamped to the
+    /// end of the source.
+    #[cfg(test)]
+    pub(crate) fn span(&self, start: usize, length: usize) -> Span {
+        let start = start.min(self.len());
+        let length = length.min(self.len() - start);
+        Span::new(Location::new(self.clone(), start), length)
+    }
+
+    /// Creates a rewriter of this source code object, which applies edits to
+    /// its contents.
+    pub fn rewriter(&self) -> Rewriter {
+        Rewriter::new(self.clone())
+    }
+
+    /// Applies a single edit to this source code object, producing a new
+    /// version of it, e.g. as the user types in an editor. Only the region
+    /// around the edit is segmented again. Fails if the edit belongs to
+    /// another source or splits a character.
+    pub fn apply_edit(&self, edit: &Edit) -> Result<Revision, RewriteError> {
+        if edit.span().source() != self {
+            return Err(RewriteError::ForeignSource(edit.clone()));
+        }
+        if edit.span().splits_char() {
+            return Err(RewriteError::SplitChar(edit.clone()));
+        }
+        Ok(Revision::new(self.clone(), edit))
+    }
 }
 
 impl fmt::Debug for Source {
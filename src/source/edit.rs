@@ -0,0 +1,254 @@
+//! This module provides textual edits on a source code and a rewriter that
+//! applies them.
+
+use super::{Location, Source, Span};
+use std::{error::Error, fmt};
+
+/// A textual edit on a source code: the contents of a span are replaced by
+/// some text. Insertions are edits with empty spans, and deletions are edits
+/// with empty replacements.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Edit {
+    /// Span whose contents are replaced.
+    span: Span,
+    /// The text replacing the span's contents.
+    replacement: String,
+}
+
+impl Edit {
+    /// Creates an edit that replaces the contents of the given span with the
+    /// given text.
+    pub fn replace<S>(span: Span, replacement: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self { span, replacement: replacement.into() }
+    }
+
+    /// Creates an edit that inserts the given text at the given location.
+    pub fn insert<S>(location: Location, text: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self::replace(Span::new(location, 0), text)
+    }
+
+    /// Creates an edit that deletes the contents of the given span.
+    pub fn delete(span: Span) -> Self {
+        Self::replace(span, "")
+    }
+
+    /// Span whose contents are replaced.
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    /// The text replacing the span's contents.
+    pub fn replacement(&self) -> &str {
+        &self.replacement
+    }
+
+    /// Returns whether this edit conflicts with another edit, i.e. whether
+    /// both touch the same string segments or both start at the same
+    /// location (and so the order they should be applied is ambiguous).
+    /// Edits of different sources never conflict.
+    pub fn conflicts(&self, other: &Edit) -> bool {
+        if self.span.source() != other.span.source() {
+            return false;
+        }
+        let start = self.span.start().position();
+        let end = start + self.span.len();
+        let other_start = other.span.start().position();
+        let other_end = other_start + other.span.len();
+        start == other_start || (start < other_end && other_start < end)
+    }
+}
+
+/// Error returned when an [`Edit`] cannot be added to a [`Rewriter`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RewriteError {
+    /// The edit refers to a source other than the rewriter's source.
+    ForeignSource(Edit),
+    /// The span of the edit splits a character, which only happens with
+    /// [`Segmentation::Bytes`](super::Segmentation::Bytes).
+    SplitChar(Edit),
+    /// The edit conflicts with an edit previously added.
+    Conflict {
+        /// The edit that could not be added.
+        edit: Edit,
+        /// The edit previously added, conflicting with the new one.
+        existing: Edit,
+    },
+}
+
+impl fmt::Display for RewriteError {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RewriteError::ForeignSource(edit) => write!(
+                fmtr,
+                "edit {} does not belong to the rewritten source",
+                edit.span()
+            ),
+            RewriteError::SplitChar(edit) => {
+                write!(fmtr, "edit {} splits a character", edit.span())
+            },
+            RewriteError::Conflict { edit, existing } => write!(
+                fmtr,
+                "edit {} conflicts with edit {}",
+                edit.span(),
+                existing.span()
+            ),
+        }
+    }
+}
+
+impl Error for RewriteError {}
+
+/// A rewriter of a source code: collects non-conflicting [`Edit`]s and
+/// produces the new contents of the source with the edits applied.
+///
+/// See [`Source::rewriter`](Source::rewriter) to create a rewriter.
+#[derive(Debug, Clone)]
+pub struct Rewriter {
+    /// The source code being rewritten.
+    source: Source,
+    /// Edits added so far, sorted by start position.
+    edits: Vec<Edit>,
+}
+
+impl Rewriter {
+    /// Creates a new rewriter of the given source, with no edits.
+    pub(super) fn new(source: Source) -> Self {
+        Self { source, edits: Vec::new() }
+    }
+
+    /// The source code being rewritten.
+    pub fn source(&self) -> &Source {
+        &self.source
+    }
+
+    /// Edits added so far, sorted by start position.
+    pub fn edits(&self) -> &[Edit] {
+        &self.edits
+    }
+
+    /// Adds an edit, failing if it belongs to another source, if it splits a
+    /// character or if it conflicts with an edit previously added. Adding an
+    /// edit equal to a previous one has no effect.
+    pub fn add(&mut self, edit: Edit) -> Result<(), RewriteError> {
+        self.check(&edit)?;
+        if !self.edits.contains(&edit) {
+            let index = self.insertion_index(&edit);
+            self.edits.insert(index, edit);
+        }
+        Ok(())
+    }
+
+    /// Adds all the given edits, or none of them if any one fails to be
+    /// added, including if they conflict with each other.
+    pub fn add_all<I>(&mut self, edits: I) -> Result<(), RewriteError>
+    where
+        I: IntoIterator<Item = Edit>,
+    {
+        let mut attempt = self.clone();
+        for edit in edits {
+            attempt.add(edit)?;
+        }
+        *self = attempt;
+        Ok(())
+    }
+
+    /// Finishes the rewriter, producing the contents of the source with all
+    /// edits applied.
+    pub fn finish(self) -> String {
+        let contents = self.source.contents();
+        let mut output = String::with_capacity(contents.len());
+        let mut offset = 0;
+        for edit in &self.edits {
+            let start = edit.span().start().byte_offset();
+            output.push_str(&contents[offset .. start]);
+            output.push_str(edit.replacement());
+            offset = edit.span().end().byte_offset();
+        }
+        output.push_str(&contents[offset ..]);
+        output
+    }
+
+    /// Checks whether the given edit can be added.
+    fn check(&self, edit: &Edit) -> Result<(), RewriteError> {
+        if *edit.span().source() != self.source {
+            return Err(RewriteError::ForeignSource(edit.clone()));
+        }
+        if edit.span().splits_char() {
+            return Err(RewriteError::SplitChar(edit.clone()));
+        }
+        let conflicting = self
+            .edits
+            .iter()
+            .find(|existing| *existing != edit && existing.conflicts(edit));
+        match conflicting {
+            Some(existing) => Err(RewriteError::Conflict {
+                edit: edit.clone(),
+                existing: existing.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Index where the given edit should be inserted to keep edits sorted.
+    fn insertion_index(&self, edit: &Edit) -> usize {
+        let position = edit.span().start().position();
+        self.edits
+            .iter()
+            .position(|existing| existing.span().start().position() > position)
+            .unwrap_or(self.edits.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Edit, RewriteError};
+    use crate::source::Source;
+
+    #[test]
+    fn apply_edits() {
+        let source = Source::new("a.lam", "\\f \\x f (f ç)");
+        let mut rewriter = source.rewriter();
+        rewriter.add(Edit::replace(source.span(11, 1), "x")).unwrap();
+        rewriter.add(Edit::insert(source.span(2, 0).start(), ".")).unwrap();
+        rewriter.add(Edit::insert(source.span(5, 0).start(), ".")).unwrap();
+        rewriter.add(Edit::delete(source.span(2, 1))).unwrap_err();
+        rewriter.add(Edit::delete(source.span(12, 1))).unwrap();
+        assert_eq!(rewriter.finish(), "\\f. \\x. f (f x");
+    }
+
+    #[test]
+    fn reject_conflicts() {
+        let source = Source::new("b.lam", "abcdef");
+        let other = Source::new("c.lam", "abcdef");
+        let mut rewriter = source.rewriter();
+        let edit = Edit::replace(source.span(1, 3), "x");
+        rewriter.add(edit.clone()).unwrap();
+        rewriter.add(edit.clone()).unwrap();
+
+        let overlap = Edit::delete(source.span(3, 2));
+        assert_eq!(
+            rewriter.add(overlap.clone()),
+            Err(RewriteError::Conflict { edit: overlap, existing: edit })
+        );
+
+        let foreign = Edit::delete(other.span(0, 1));
+        assert_eq!(
+            rewriter.add(foreign.clone()),
+            Err(RewriteError::ForeignSource(foreign))
+        );
+
+        let edits = vec![
+            Edit::insert(source.span(0, 0).start(), "y"),
+            Edit::insert(source.span(2, 0).start(), "z"),
+        ];
+        assert!(rewriter.add_all(edits).is_err());
+        assert_eq!(rewriter.edits().len(), 1);
+        assert_eq!(rewriter.finish(), "axef");
+    }
+}
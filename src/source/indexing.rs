@@ -21,6 +21,8 @@ use std::{
 /// Builder of an [`IndexArray`].
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
 pub struct IndexArrayBuilder {
+    /// Number of leading indices equal to their meta-indices, not stored.
+    identity: usize,
     /// 8 bit indices, which will be sorted by the end.
     as_u8: Vec<u8>,
     /// 16 bit indices, which will be sorted by the end.
@@ -39,7 +41,8 @@ impl IndexArrayBuilder {
 
     /// The length of this builder.
     pub fn len(&self) -> usize {
-        self.as_u8.len()
+        self.identity
+            + self.as_u8.len()
             + self.as_u16.len()
             + self.as_u32.len()
             + self.as_u64.len()
@@ -47,7 +50,9 @@ impl IndexArrayBuilder {
 
     /// Pushes a new index onto the builder.
     pub fn push(&mut self, index: usize) -> &mut Self {
-        if let Ok(i) = u8::try_from(index) {
+        if index == self.identity && self.len() == self.identity {
+            self.identity += 1;
+        } else if let Ok(i) = u8::try_from(index) {
             self.as_u8.push(i);
         } else if let Ok(i) = u16::try_from(index) {
             self.as_u16.push(i);
@@ -69,6 +74,7 @@ impl IndexArrayBuilder {
         self.as_u64.sort();
 
         IndexArray {
+            identity: self.identity,
             as_u8: self.as_u8.into(),
             as_u16: self.as_u16.into(),
             as_u32: self.as_u32.into(),
@@ -84,9 +90,12 @@ impl From<IndexArrayBuilder> for IndexArray {
 }
 
 /// A smart ordered array of indices, which tries to use space as little as
-/// possible.
+/// possible. Leading indices equal to their meta-indices (e.g. the segments
+/// of an ASCII prefix) are not stored at all.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct IndexArray {
+    /// Number of leading indices equal to their meta-indices.
+    identity: usize,
     /// Indices that fit 8 bits.
     as_u8: Box<[u8]>,
     /// Indices that fit 16 bits.
@@ -100,7 +109,8 @@ pub struct IndexArray {
 impl IndexArray {
     /// Length of this array.
     pub fn len(&self) -> usize {
-        self.as_u8.len()
+        self.identity
+            + self.as_u8.len()
             + self.as_u16.len()
             + self.as_u32.len()
             + self.as_u64.len()
@@ -124,6 +134,10 @@ impl IndexArray {
     /// Gets an index stored in the array given this meta-index, returning
     /// `None` if out of bounds.
     pub fn get(&self, mut meta_index: usize) -> Option<usize> {
+        if meta_index < self.identity {
+            return Some(meta_index);
+        }
+        meta_index -= self.identity;
         if let Some(&i) = self.as_u8.get(meta_index) {
             return Some(i as usize);
         }
@@ -163,9 +177,35 @@ impl IndexArray {
         Err(error)
     }
 
+    /// Creates a copy of this array where the indices at the given range of
+    /// meta-indices are replaced by the given indices, and the indices after
+    /// the range are shifted by the given amount. Indices must remain sorted.
+    pub fn splice<I>(
+        &self,
+        range: Range<usize>,
+        replacement: I,
+        shift: isize,
+    ) -> IndexArray
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        let mut builder = IndexArrayBuilder::new();
+        for index in self.iter().take(range.start) {
+            builder.push(index);
+        }
+        for index in replacement {
+            builder.push(index);
+        }
+        for index in self.iter().skip(range.end) {
+            builder.push((index as isize + shift) as usize);
+        }
+        builder.finish()
+    }
+
     /// Iterates over the indices stored in this array.
-    pub fn iter(&self) -> IndexArrayIter {
+    pub fn iter(&self) -> IndexArrayIter<'_> {
         IndexArrayIter {
+            identity: 0 .. self.identity,
             as_u8: self.as_u8.iter(),
             as_u16: self.as_u16.iter(),
             as_u32: self.as_u32.iter(),
@@ -186,6 +226,7 @@ impl<'array> IntoIterator for &'array IndexArray {
 /// Iterator over an array of indices. Double-ended and sized.
 #[derive(Debug)]
 pub struct IndexArrayIter<'array> {
+    identity: Range<usize>,
     as_u8: slice::Iter<'array, u8>,
     as_u16: slice::Iter<'array, u16>,
     as_u32: slice::Iter<'array, u32>,
@@ -196,6 +237,9 @@ impl<'array> Iterator for IndexArrayIter<'array> {
     type Item = usize;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some(i) = self.identity.next() {
+            return Some(i);
+        }
         if let Some(&i) = self.as_u8.next() {
             return Some(i as usize);
         }
@@ -209,7 +253,8 @@ impl<'array> Iterator for IndexArrayIter<'array> {
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let len = self.as_u8.len()
+        let len = self.identity.len()
+            + self.as_u8.len()
             + self.as_u16.len()
             + self.as_u32.len()
             + self.as_u64.len();
@@ -219,16 +264,19 @@ impl<'array> Iterator for IndexArrayIter<'array> {
 
 impl<'array> DoubleEndedIterator for IndexArrayIter<'array> {
     fn next_back(&mut self) -> Option<Self::Item> {
-        if let Some(&i) = self.as_u64.next() {
+        if let Some(&i) = self.as_u64.next_back() {
             return Some(i as usize);
         }
-        if let Some(&i) = self.as_u32.next() {
+        if let Some(&i) = self.as_u32.next_back() {
             return Some(i as usize);
         }
-        if let Some(&i) = self.as_u16.next() {
+        if let Some(&i) = self.as_u16.next_back() {
             return Some(i as usize);
         }
-        self.as_u8.next().map(|&i| i as usize)
+        if let Some(&i) = self.as_u8.next_back() {
+            return Some(i as usize);
+        }
+        self.identity.next_back()
     }
 }
 
@@ -361,4 +409,42 @@ mod test {
         assert_eq!(array.binary_search(9), Ok(5));
         assert_eq!(array.binary_search(10), Err(6));
     }
+
+    #[test]
+    fn iter_back() {
+        let mut builder = IndexArrayBuilder::default();
+        builder.push(3).push(5).push(300).push(301).push(70000);
+        let array = builder.finish();
+        assert_eq!(
+            array.iter().rev().collect::<Vec<_>>(),
+            [70000, 301, 300, 5, 3]
+        );
+        let mut iter = array.iter();
+        assert_eq!(iter.next(), Some(3));
+        assert_eq!(iter.next_back(), Some(70000));
+        assert_eq!(iter.next_back(), Some(301));
+        assert_eq!(iter.next(), Some(5));
+        assert_eq!(iter.next_back(), Some(300));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn identity_prefix() {
+        let mut builder = IndexArrayBuilder::default();
+        builder.push(0).push(1).push(2).push(4).push(300).push(301);
+        assert_eq!(builder.len(), 6);
+        let array = builder.finish();
+        assert_eq!(array.get(1), Some(1));
+        assert_eq!(array.get(3), Some(4));
+        assert_eq!(array.get(6), None);
+        assert_eq!(array.binary_search(2), Ok(2));
+        assert_eq!(array.binary_search(3), Err(3));
+        assert_eq!(array.iter().collect::<Vec<_>>(), [0, 1, 2, 4, 300, 301]);
+        assert_eq!(
+            array.iter().rev().collect::<Vec<_>>(),
+            [301, 300, 4, 2, 1, 0]
+        );
+        assert_eq!(array.iter().len(), 6);
+    }
 }
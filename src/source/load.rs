@@ -0,0 +1,465 @@
+//! This module provides loading of source code from the filesystem or from a
+//! reader, decoding its contents into UTF-8.
+
+use super::{Segmentation, Source};
+use std::{char, error::Error, fmt, fs, io, path::Path};
+
+/// UTF-8 byte order mark.
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// UTF-16 little-endian byte order mark.
+const UTF16_LE_BOM: &[u8] = b"\xff\xfe";
+
+/// UTF-16 big-endian byte order mark.
+const UTF16_BE_BOM: &[u8] = b"\xfe\xff";
+
+/// Character encoding of a loaded source code, before it was decoded into
+/// UTF-8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Encoding {
+    /// UTF-8, the encoding of sources created in memory.
+    Utf8,
+    /// UTF-16, little-endian.
+    Utf16Le,
+    /// UTF-16, big-endian.
+    Utf16Be,
+    /// ISO-8859-1, where every byte is the unicode scalar value of the same
+    /// number.
+    Latin1,
+}
+
+impl Encoding {
+    /// The byte order mark of this encoding, if it has any.
+    pub fn bom(self) -> Option<&'static [u8]> {
+        match self {
+            Encoding::Utf8 => Some(UTF8_BOM),
+            Encoding::Utf16Le => Some(UTF16_LE_BOM),
+            Encoding::Utf16Be => Some(UTF16_BE_BOM),
+            Encoding::Latin1 => None,
+        }
+    }
+
+    /// Detects the encoding of the given bytes by their byte order mark, if
+    /// they start with one.
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        [Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be]
+            .iter()
+            .copied()
+            .find(|encoding| {
+                encoding.bom().is_some_and(|bom| bytes.starts_with(bom))
+            })
+    }
+
+    /// Number of bytes the given UTF-8 text takes in this encoding.
+    pub(super) fn encoded_len(self, text: &str) -> usize {
+        match self {
+            Encoding::Utf8 => text.len(),
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                text.encode_utf16().count() * 2
+            },
+            Encoding::Latin1 => text.chars().count(),
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        fmtr.write_str(match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Latin1 => "ISO-8859-1",
+        })
+    }
+}
+
+/// Loader of source code objects, decoding their contents.
+///
+/// By default, the encoding is detected by the byte order mark, falling back
+/// to UTF-8 when there is none. An encoding can also be chosen explicitly,
+/// which is the only way to load Latin-1. The byte order mark of the encoding
+/// used, if present, is stripped from the contents.
+///
+/// Loaded sources remember their original encoding, so positions can still
+/// be reported against the original bytes, see
+/// [`Location::original_byte_offset`](super::Location::original_byte_offset).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Loader {
+    /// Encoding chosen explicitly, if any.
+    encoding: Option<Encoding>,
+    /// Unit the contents of loaded sources are segmented into.
+    segmentation: Segmentation,
+}
+
+impl Loader {
+    /// Creates a new loader detecting the encoding of sources, and
+    /// segmenting them into extended grapheme clusters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Encoding chosen explicitly, if any.
+    pub fn encoding(&self) -> Option<Encoding> {
+        self.encoding
+    }
+
+    /// Sets the encoding of loaded sources. With `None`, the encoding is
+    /// detected by the byte order mark.
+    pub fn set_encoding(&mut self, encoding: Option<Encoding>) -> &mut Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Unit the contents of loaded sources are segmented into.
+    pub fn segmentation(&self) -> Segmentation {
+        self.segmentation
+    }
+
+    /// Sets the unit the contents of loaded sources are segmented into.
+    pub fn set_segmentation(
+        &mut self,
+        segmentation: Segmentation,
+    ) -> &mut Self {
+        self.segmentation = segmentation;
+        self
+    }
+
+    /// Loads a source code object from the file at the given path, using the
+    /// path as its name.
+    pub fn load_path<P>(&self, path: P) -> Result<Source, LoadError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let name = path.display().to_string();
+        match fs::File::open(path) {
+            Ok(file) => self.load_reader(name, file),
+            Err(error) => {
+                Err(LoadError { name, kind: LoadErrorKind::Io(error) })
+            },
+        }
+    }
+
+    /// Loads a source code object with the given name from the whole
+    /// contents of a reader, e.g. the standard input.
+    pub fn load_reader<S, R>(
+        &self,
+        name: S,
+        mut reader: R,
+    ) -> Result<Source, LoadError>
+    where
+        S: Into<String>,
+        R: io::Read,
+    {
+        let name = name.into();
+        let mut bytes = Vec::new();
+        if let Err(error) = reader.read_to_end(&mut bytes) {
+            return Err(LoadError { name, kind: LoadErrorKind::Io(error) });
+        }
+        let detected = Encoding::detect(&bytes);
+        let encoding = self.encoding.or(detected).unwrap_or(Encoding::Utf8);
+        let bom = match encoding.bom() {
+            Some(bom) if bytes.starts_with(bom) => bom.len(),
+            _ => 0,
+        };
+        match decode(encoding, &bytes[bom ..]) {
+            Ok(contents) => Ok(Source::from_parts(
+                name,
+                contents,
+                self.segmentation,
+                encoding,
+                bom > 0,
+            )),
+            Err(mut kind) => {
+                kind.shift(bom);
+                Err(LoadError { name, kind })
+            },
+        }
+    }
+}
+
+/// Decodes the given bytes, without byte order mark, from the given
+/// encoding. Offsets in errors are relative to the given bytes.
+fn decode(encoding: Encoding, bytes: &[u8]) -> Result<String, LoadErrorKind> {
+    match encoding {
+        Encoding::Utf8 => match std::str::from_utf8(bytes) {
+            Ok(contents) => Ok(contents.to_owned()),
+            Err(error) => Err(LoadErrorKind::InvalidUtf8 {
+                offset: error.valid_up_to(),
+                length: error.error_len(),
+            }),
+        },
+        Encoding::Utf16Le | Encoding::Utf16Be => {
+            let chunks = bytes.chunks_exact(2);
+            if !chunks.remainder().is_empty() {
+                let offset = bytes.len() - 1;
+                return Err(LoadErrorKind::InvalidUtf16 { offset });
+            }
+            let units = chunks.map(|chunk| {
+                let pair = [chunk[0], chunk[1]];
+                if encoding == Encoding::Utf16Le {
+                    u16::from_le_bytes(pair)
+                } else {
+                    u16::from_be_bytes(pair)
+                }
+            });
+            let mut contents = String::with_capacity(bytes.len() / 2);
+            let mut decoded_units = 0;
+            for result in char::decode_utf16(units) {
+                match result {
+                    Ok(ch) => {
+                        contents.push(ch);
+                        decoded_units += ch.len_utf16();
+                    },
+                    Err(_) => {
+                        let offset = decoded_units * 2;
+                        return Err(LoadErrorKind::InvalidUtf16 { offset });
+                    },
+                }
+            }
+            Ok(contents)
+        },
+        Encoding::Latin1 => {
+            Ok(bytes.iter().map(|&byte| char::from(byte)).collect())
+        },
+    }
+}
+
+/// Error returned when a source cannot be loaded, e.g. by
+/// [`Source::from_path`].
+#[derive(Debug)]
+pub struct LoadError {
+    /// Name the source would have.
+    pub name: String,
+    /// What went wrong.
+    pub kind: LoadErrorKind,
+}
+
+/// Kind of a [`LoadError`].
+#[derive(Debug)]
+pub enum LoadErrorKind {
+    /// The contents could not be read.
+    Io(io::Error),
+    /// The contents are not valid UTF-8.
+    InvalidUtf8 {
+        /// Offset, in bytes, of the first invalid byte sequence.
+        offset: usize,
+        /// Length, in bytes, of the invalid sequence, or `None` if the
+        /// contents end in the middle of a sequence.
+        length: Option<usize>,
+    },
+    /// The contents are not valid UTF-16: there is an unpaired surrogate,
+    /// or an odd number of bytes.
+    InvalidUtf16 {
+        /// Offset, in bytes, of the first invalid code unit, or of the last
+        /// byte if their number is odd.
+        offset: usize,
+    },
+}
+
+impl LoadErrorKind {
+    /// Shifts the offsets of this error by the given number of bytes.
+    fn shift(&mut self, bytes: usize) {
+        match self {
+            LoadErrorKind::Io(_) => (),
+            LoadErrorKind::InvalidUtf8 { offset, .. }
+            | LoadErrorKind::InvalidUtf16 { offset } => *offset += bytes,
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            LoadErrorKind::Io(error) => {
+                write!(fmtr, "{}: {}", self.name, error)
+            },
+            LoadErrorKind::InvalidUtf8 { offset, length: Some(length) } => {
+                write!(
+                    fmtr,
+                    "{}: invalid UTF-8 sequence of {} byte(s) at byte {}",
+                    self.name, length, offset
+                )
+            },
+            LoadErrorKind::InvalidUtf8 { offset, length: None } => write!(
+                fmtr,
+                "{}: incomplete UTF-8 sequence at byte {}",
+                self.name, offset
+            ),
+            LoadErrorKind::InvalidUtf16 { offset } => write!(
+                fmtr,
+                "{}: invalid UTF-16 code unit at byte {}",
+                self.name, offset
+            ),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            LoadErrorKind::Io(error) => Some(error),
+            LoadErrorKind::InvalidUtf8 { .. }
+            | LoadErrorKind::InvalidUtf16 { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Encoding, LoadErrorKind, Loader};
+    use crate::source::{Segmentation, Source};
+    use std::{env, fs, io, process};
+
+    fn utf16(text: &str, encoding: Encoding) -> Vec<u8> {
+        let mut bytes = encoding.bom().unwrap().to_vec();
+        for unit in text.encode_utf16() {
+            match encoding {
+                Encoding::Utf16Le => {
+                    bytes.extend_from_slice(&unit.to_le_bytes())
+                },
+                _ => bytes.extend_from_slice(&unit.to_be_bytes()),
+            }
+        }
+        bytes
+    }
+
+    struct Failing;
+
+    impl io::Read for Failing {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::Other, "disk on fire"))
+        }
+    }
+
+    #[test]
+    fn from_reader() {
+        let source =
+            Source::from_reader("<stdin>", "\\x. x".as_bytes()).unwrap();
+        assert_eq!(source.name(), "<stdin>");
+        assert_eq!(source.contents(), "\\x. x");
+
+        let error =
+            Source::from_reader("a.lam", &b"ab\xe7\x29"[..]).unwrap_err();
+        assert!(matches!(
+            error.kind,
+            LoadErrorKind::InvalidUtf8 { offset: 2, length: Some(1) }
+        ));
+        assert_eq!(
+            error.to_string(),
+            "a.lam: invalid UTF-8 sequence of 1 byte(s) at byte 2"
+        );
+        let error = Source::from_reader("b.lam", &b"x\xc3"[..]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "b.lam: incomplete UTF-8 sequence at byte 1"
+        );
+
+        let error = Source::from_reader("c.lam", Failing).unwrap_err();
+        assert!(matches!(error.kind, LoadErrorKind::Io(_)));
+        assert_eq!(error.to_string(), "c.lam: disk on fire");
+    }
+
+    #[test]
+    fn from_path() {
+        let path = env::temp_dir()
+            .join(format!("bittongue-load-{}.lam", process::id()));
+        fs::write(&path, "f (g x)\n").unwrap();
+        let source = Source::from_path(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(source.name(), path.display().to_string());
+        assert_eq!(source.contents(), "f (g x)\n");
+
+        let error = Source::from_path(&path).unwrap_err();
+        match error.kind {
+            LoadErrorKind::Io(error) => {
+                assert_eq!(error.kind(), io::ErrorKind::NotFound)
+            },
+            kind => panic!("unexpected error {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn detect_encoding() {
+        let text = "\\x. x\n\u{1d49c}é";
+        let mut utf8 = b"\xef\xbb\xbf".to_vec();
+        utf8.extend_from_slice(text.as_bytes());
+        let inputs = [
+            (utf8, Encoding::Utf8),
+            (utf16(text, Encoding::Utf16Le), Encoding::Utf16Le),
+            (utf16(text, Encoding::Utf16Be), Encoding::Utf16Be),
+        ];
+        for (bytes, encoding) in &inputs {
+            let source = Source::from_reader("a.lam", &bytes[..]).unwrap();
+            assert_eq!(source.contents(), text);
+            assert_eq!(source.encoding(), *encoding);
+            assert!(source.has_bom());
+        }
+
+        let source = Source::from_reader("b.lam", "x".as_bytes()).unwrap();
+        assert_eq!(source.encoding(), Encoding::Utf8);
+        assert!(!source.has_bom());
+    }
+
+    #[test]
+    fn explicit_encoding() {
+        let mut loader = Loader::new();
+        loader.set_encoding(Some(Encoding::Latin1));
+        let bytes = b"\xef\xbb\xbf caf\xe9";
+        let source = loader.load_reader("a.lam", &bytes[..]).unwrap();
+        assert_eq!(source.contents(), "ï»¿ café");
+        assert_eq!(source.encoding(), Encoding::Latin1);
+        assert!(!source.has_bom());
+        assert_eq!(source.len(), 8);
+
+        loader.set_segmentation(Segmentation::Bytes);
+        let source = loader.load_reader("a.lam", &bytes[..]).unwrap();
+        assert_eq!(source.segmentation(), Segmentation::Bytes);
+        assert_eq!(source.len(), 12);
+
+        loader.set_encoding(Some(Encoding::Utf16Be));
+        let source = loader.load_reader("b.lam", &b"\x00x\x00y"[..]).unwrap();
+        assert_eq!(source.contents(), "xy");
+        assert!(!source.has_bom());
+        let error = loader.load_reader("c.lam", &b"\xfe\xff\x00x\x00"[..]);
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            "c.lam: invalid UTF-16 code unit at byte 4"
+        );
+        let bytes = b"\xfe\xff\x00x\xdc\x00\x00y";
+        let error = loader.load_reader("d.lam", &bytes[..]).unwrap_err();
+        assert!(matches!(
+            error.kind,
+            LoadErrorKind::InvalidUtf16 { offset: 4 }
+        ));
+
+        let bytes = b"\xef\xbb\xbfx\xff";
+        let error = Source::from_reader("e.lam", &bytes[..]).unwrap_err();
+        assert!(matches!(
+            error.kind,
+            LoadErrorKind::InvalidUtf8 { offset: 4, length: Some(1) }
+        ));
+    }
+
+    #[test]
+    fn original_offsets() {
+        let text = "é\u{1d49c} x";
+        let offsets = |source: &Source| {
+            let mut reader = source.reader();
+            let mut offsets = vec![reader.location().original_byte_offset()];
+            while reader.next() {
+                offsets.push(reader.location().original_byte_offset());
+            }
+            offsets
+        };
+        let source = Source::new("a.lam", text);
+        assert_eq!(offsets(&source), [0, 2, 6, 7, 8]);
+        let bytes = utf16(text, Encoding::Utf16Le);
+        let source = Source::from_reader("b.lam", &bytes[..]).unwrap();
+        assert_eq!(offsets(&source), [2, 4, 8, 10, 12]);
+        let mut loader = Loader::new();
+        loader.set_encoding(Some(Encoding::Latin1));
+        let source = loader.load_reader("c.lam", &b"\xe9 x"[..]).unwrap();
+        assert_eq!(offsets(&source), [0, 1, 2, 3]);
+    }
+}
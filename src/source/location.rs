@@ -1,6 +1,6 @@
 //! This module provides means of tracking location in a source code.
 
-use super::{Source, Span};
+use super::{Encoding, Source, Span};
 use std::fmt;
 
 /// The location in a source code.
@@ -27,6 +27,35 @@ impl Location {
         self.position
     }
 
+    /// The position of this location in the source code in terms of bytes,
+    /// i.e. the index in [`Source::contents`] where the string segment
+    /// starts.
+    pub fn byte_offset(&self) -> usize {
+        self.source.inner.segments.index(self.position)
+    }
+
+    /// The position of this location in terms of bytes of the original
+    /// contents, before they were decoded from their
+    /// [encoding](Source::encoding), including the byte order mark, if any.
+    /// Unlike [`byte_offset`](Location::byte_offset), this takes time
+    /// proportional to the offset for encodings other than UTF-8. A location
+    /// splitting a character is moved back to the start of the character,
+    /// unless the encoding is UTF-8.
+    pub fn original_byte_offset(&self) -> usize {
+        let encoding = self.source.encoding();
+        let bom = match encoding.bom() {
+            Some(bom) if self.source.has_bom() => bom.len(),
+            _ => 0,
+        };
+        let floor = self.source.floor_char_offset(self.position);
+        let split = match encoding {
+            Encoding::Utf8 => self.byte_offset() - floor,
+            _ => 0,
+        };
+        let before = &self.source.contents()[.. floor];
+        bom + encoding.encoded_len(before) + split
+    }
+
     /// The source code object this location refers to.
     pub fn source(&self) -> &Source {
         &self.source
@@ -51,6 +80,21 @@ impl Location {
         column
     }
 
+    /// Finds the column of this location in the source code, counted in
+    /// unicode scalar values (`char`s) rather than string segments. A
+    /// location splitting a character is in the column of the character.
+    pub fn char_column(&self) -> usize {
+        self.line_prefix().chars().count()
+    }
+
+    /// Finds the column of this location in the source code, counted in
+    /// UTF-16 code units rather than string segments, as required by e.g.
+    /// the Language Server Protocol. A location splitting a character is in
+    /// the column of the character.
+    pub fn utf16_column(&self) -> usize {
+        self.line_prefix().encode_utf16().count()
+    }
+
     /// Creates a [`Span`] containing the whole line this location is in.
     pub fn line_span(&self) -> Span {
         let line = self.line();
@@ -61,6 +105,15 @@ impl Location {
             .unwrap_or(self.source().len());
         Span::new(Self::new(self.source.clone(), init), end - init)
     }
+
+    /// The contents of the line of this location before the character it is
+    /// in.
+    fn line_prefix(&self) -> &str {
+        let line_start = self.source.line_start(self.line());
+        let from = self.source.floor_char_offset(line_start);
+        let to = self.source.floor_char_offset(self.position);
+        &self.source.contents()[from .. to]
+    }
 }
 
 impl fmt::Debug for Location {
@@ -0,0 +1,327 @@
+//! This module provides a registry of many sources sharing a single, global
+//! position space, in which spans are compact and cheap to copy.
+
+use super::{Location, Source, Span};
+use std::{collections::HashMap, error::Error, fmt, slice};
+
+/// A compact span in the position space of a [`SourceMap`]: just the global
+/// start and end positions, without a reference to the source. Copying it is
+/// free, unlike cloning a [`Span`], which increments the reference counter
+/// of its source.
+///
+/// Compact spans only make sense together with the map they were created
+/// by. See [`SourceMap::compact`] to create one, and [`SourceMap::span`] to
+/// resolve one back into a [`Span`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CompactSpan {
+    /// Global start position.
+    start: u32,
+    /// Global end position.
+    end: u32,
+}
+
+impl CompactSpan {
+    /// Global start position of this span.
+    pub fn start(self) -> u32 {
+        self.start
+    }
+
+    /// Global end position of this span.
+    pub fn end(self) -> u32 {
+        self.end
+    }
+
+    /// The length of this span in string segments.
+    pub fn len(self) -> usize {
+        (self.end - self.start) as usize
+    }
+
+    /// Returns whether this span contains no string segments.
+    pub fn is_empty(self) -> bool {
+        self.start == self.end
+    }
+
+    /// Joins two spans into a span covering both and everything between
+    /// them. The spans should belong to the same source, otherwise the joined
+    /// span cannot be resolved.
+    pub fn join(self, other: Self) -> Self {
+        Self {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+}
+
+/// Entry of a source in a [`SourceMap`].
+#[derive(Debug, Clone)]
+struct Entry {
+    /// Global position of the source's start.
+    base: u32,
+    /// The source itself.
+    source: Source,
+}
+
+/// A registry of many sources, each assigned to a contiguous range of one
+/// global position space. Positions of a source are offset by the start of
+/// its range, and the end of a source (where a span at end of input lies) has
+/// a position of its own, distinct from the start of the next source.
+///
+/// # Example
+/// ```rust
+/// use bittongue::source::{Source, SourceMap};
+///
+/// # fn main() {
+/// let mut map = SourceMap::new();
+/// let first = map.add(Source::new("a.lam", "x y")).unwrap();
+/// let second = map.add(Source::new("b.lam", "z")).unwrap();
+/// assert_eq!((first.start(), first.end()), (0, 3));
+/// assert_eq!((second.start(), second.end()), (4, 5));
+///
+/// let span = map.span(second).unwrap();
+/// assert_eq!(span.source().name(), "b.lam");
+/// assert_eq!(span.as_str(), "z");
+/// assert_eq!(map.compact(&span), Some(second));
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    /// Entries of the sources, sorted by their base positions.
+    entries: Vec<Entry>,
+    /// Index of each source's entry.
+    indices: HashMap<Source, usize>,
+}
+
+impl SourceMap {
+    /// Creates a new, empty source map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of sources in this map.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether this map has no sources.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds a source to this map, returning the span covering its whole
+    /// contents. Adding a source already in the map just returns its span.
+    /// Fails if the global position space is exhausted.
+    pub fn add(
+        &mut self,
+        source: Source,
+    ) -> Result<CompactSpan, SourceMapFull> {
+        if let Some(span) = self.source_span(&source) {
+            return Ok(span);
+        }
+        let base = match self.entries.last() {
+            Some(entry) => entry.base as usize + entry.source.len() + 1,
+            None => 0,
+        };
+        let end = base + source.len();
+        if end > u32::MAX as usize {
+            return Err(SourceMapFull { source });
+        }
+        self.indices.insert(source.clone(), self.entries.len());
+        self.entries.push(Entry { base: base as u32, source });
+        Ok(CompactSpan { start: base as u32, end: end as u32 })
+    }
+
+    /// Returns whether the given source is in this map.
+    pub fn contains(&self, source: &Source) -> bool {
+        self.indices.contains_key(source)
+    }
+
+    /// The span covering the whole contents of the given source, if it is in
+    /// this map.
+    pub fn source_span(&self, source: &Source) -> Option<CompactSpan> {
+        let entry = &self.entries[*self.indices.get(source)?];
+        let end = entry.base + entry.source.len() as u32;
+        Some(CompactSpan { start: entry.base, end })
+    }
+
+    /// Creates an iterator over the sources in this map, in the order they
+    /// were added.
+    pub fn iter(&self) -> SourceMapIter<'_> {
+        SourceMapIter { inner: self.entries.iter() }
+    }
+
+    /// Finds the source containing the given global position, if any.
+    pub fn lookup_source(&self, position: u32) -> Option<&Source> {
+        self.lookup(position).map(|entry| &entry.source)
+    }
+
+    /// Converts a location into a global position, if its source is in this
+    /// map.
+    pub fn compact_location(&self, location: &Location) -> Option<u32> {
+        let entry = &self.entries[*self.indices.get(location.source())?];
+        Some(entry.base + location.position() as u32)
+    }
+
+    /// Converts a span into a compact span, if its source is in this map.
+    pub fn compact(&self, span: &Span) -> Option<CompactSpan> {
+        let start = self.compact_location(&span.start())?;
+        Some(CompactSpan { start, end: start + span.len() as u32 })
+    }
+
+    /// Resolves a global position into a location, if some source in this
+    /// map contains it.
+    pub fn location(&self, position: u32) -> Option<Location> {
+        let entry = self.lookup(position)?;
+        let local = (position - entry.base) as usize;
+        Some(Location::new(entry.source.clone(), local))
+    }
+
+    /// Resolves a compact span into a span, if some source in this map
+    /// contains both its start and its end.
+    pub fn span(&self, span: CompactSpan) -> Option<Span> {
+        let entry = self.lookup(span.start)?;
+        if span.end > entry.base + entry.source.len() as u32 {
+            return None;
+        }
+        let start = Location::new(
+            entry.source.clone(),
+            (span.start - entry.base) as usize,
+        );
+        Some(Span::new(start, span.len()))
+    }
+
+    /// Finds the entry of the source containing the given global position.
+    fn lookup(&self, position: u32) -> Option<&Entry> {
+        let index = self
+            .entries
+            .partition_point(|entry| entry.base <= position)
+            .checked_sub(1)?;
+        let entry = &self.entries[index];
+        if position as usize <= entry.base as usize + entry.source.len() {
+            Some(entry)
+        } else {
+            None
+        }
+    }
+}
+
+/// Iterator over the sources of a [`SourceMap`].
+#[derive(Debug, Clone)]
+pub struct SourceMapIter<'map> {
+    /// The inner iterator over the entries.
+    inner: slice::Iter<'map, Entry>,
+}
+
+impl<'map> Iterator for SourceMapIter<'map> {
+    type Item = &'map Source;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|entry| &entry.source)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'map> DoubleEndedIterator for SourceMapIter<'map> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|entry| &entry.source)
+    }
+}
+
+impl<'map> ExactSizeIterator for SourceMapIter<'map> {}
+
+impl<'map> IntoIterator for &'map SourceMap {
+    type Item = &'map Source;
+    type IntoIter = SourceMapIter<'map>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Error returned when a source does not fit in the global position space of
+/// a [`SourceMap`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SourceMapFull {
+    /// The source that could not be added.
+    pub source: Source,
+}
+
+impl fmt::Display for SourceMapFull {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmtr, "source {} does not fit in the source map", self.source)
+    }
+}
+
+impl Error for SourceMapFull {}
+
+#[cfg(test)]
+mod test {
+    use super::SourceMap;
+    use crate::source::Source;
+
+    #[test]
+    fn round_trip() {
+        let first = Source::new("a.lam", "\\x. é");
+        let second = Source::new("b.lam", "");
+        let third = Source::new("c.lam", "f x");
+        let other = Source::new("d.lam", "y");
+        let mut map = SourceMap::new();
+        for source in &[&first, &second, &third] {
+            map.add((*source).clone()).unwrap();
+        }
+        assert_eq!(
+            map.add(second.clone()).unwrap(),
+            map.source_span(&second).unwrap()
+        );
+        assert_eq!(map.len(), 3);
+        assert!(!map.contains(&other));
+        assert_eq!(
+            map.iter().map(Source::name).collect::<Vec<_>>(),
+            ["a.lam", "b.lam", "c.lam"]
+        );
+
+        let spans = [
+            first.span(4, 1),
+            first.span(5, 0),
+            second.span(0, 0),
+            third.span(0, 3),
+        ];
+        let compact = spans
+            .iter()
+            .map(|span| map.compact(span).unwrap())
+            .map(|span| (span.start(), span.end()))
+            .collect::<Vec<_>>();
+        assert_eq!(compact, [(4, 5), (5, 5), (6, 6), (7, 10)]);
+        for span in &spans {
+            assert_eq!(
+                map.span(map.compact(span).unwrap()).as_ref(),
+                Some(span)
+            );
+        }
+        assert_eq!(map.compact(&other.span(0, 1)), None);
+    }
+
+    #[test]
+    fn lookup() {
+        let first = Source::new("a.lam", "x y");
+        let second = Source::new("b.lam", "z");
+        let mut map = SourceMap::new();
+        let first_span = map.add(first.clone()).unwrap();
+        let second_span = map.add(second.clone()).unwrap();
+
+        assert_eq!(map.lookup_source(3), Some(&first));
+        assert_eq!(map.lookup_source(4), Some(&second));
+        assert_eq!(map.lookup_source(6), None);
+        assert_eq!(map.location(2), Some(first.span(2, 0).start()));
+        assert_eq!(map.compact_location(&second.span(1, 0).start()), Some(5));
+
+        let joined = first_span.join(second_span);
+        assert_eq!((joined.start(), joined.end(), joined.len()), (0, 5, 5));
+        assert_eq!(map.span(joined), None);
+        let inner = map.compact(&first.span(0, 1)).unwrap();
+        let outer = map.compact(&first.span(2, 1)).unwrap();
+        assert_eq!(map.span(inner.join(outer)), Some(first.span(0, 3)));
+    }
+}
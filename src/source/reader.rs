@@ -24,7 +24,7 @@ impl Reader {
 
     /// Is the end of file reached?
     pub fn is_eof(&self) -> bool {
-        self.current().is_none()
+        self.position >= self.source.len()
     }
 
     /// Position in string segments that the reader is currently at.
@@ -32,11 +32,24 @@ impl Reader {
         self.position
     }
 
-    /// The current string segment rendered.
+    /// The current string segment rendered. With
+    /// [`Segmentation::Bytes`](super::Segmentation::Bytes), this is `None`
+    /// on bytes that are not a whole character.
     pub fn current(&self) -> Option<&GraphemeCluster> {
         self.source.get(self.position)
     }
 
+    /// The first byte of the current string segment, i.e. the current byte
+    /// with [`Segmentation::Bytes`](super::Segmentation::Bytes).
+    pub fn current_byte(&self) -> Option<u8> {
+        if self.is_eof() {
+            None
+        } else {
+            let index = self.source.inner.segments.index(self.position);
+            Some(self.source.contents().as_bytes()[index])
+        }
+    }
+
     /// A string segment from current position until `current + additional`,
     /// rendered.
     pub fn current_to(&self, additional: usize) -> Option<&str> {
@@ -76,6 +89,7 @@ impl Reader {
     }
 
     /// Advances the stream by 1 and returns whether it did move.
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> bool {
         self.advance(1) == 1
     }
@@ -107,7 +121,7 @@ impl Reader {
     where
         F: FnOnce(&GraphemeCluster) -> bool,
     {
-        self.current().map_or(false, tester)
+        self.current().is_some_and(tester)
     }
 
     /// Tests if the current grapheme cluster satisfies the given function.
@@ -0,0 +1,294 @@
+//! This module provides incremental application of edits to a source code,
+//! producing a new version of it without segmenting it all over again.
+
+use super::{Edit, Location, Source, SourceInner, Span};
+use std::sync::Arc;
+
+/// A new version of a source code, produced by applying a single [`Edit`]
+/// to it, together with the means to translate locations and spans of the
+/// previous version to the new one.
+///
+/// Only the region around the edit is segmented again into string segments,
+/// since segment boundaries can shift locally (e.g. inserting a combining
+/// accent merges it with the previous segment). The indices of the rest of
+/// the source are just shifted.
+///
+/// See [`Source::apply_edit`] to create a revision.
+#[derive(Debug, Clone)]
+pub struct Revision {
+    /// The previous version of the source.
+    previous: Source,
+    /// The new version of the source.
+    source: Source,
+    /// Byte offset where the replaced contents started.
+    start: usize,
+    /// Byte offset where the replaced contents ended, in the previous
+    /// version.
+    old_end: usize,
+    /// Byte offset where the replacement ends, in the new version.
+    new_end: usize,
+}
+
+impl Revision {
+    /// Applies the given edit to its source, which must be the given
+    /// previous version.
+    pub(super) fn new(previous: Source, edit: &Edit) -> Self {
+        let inner = &previous.inner;
+        let span = edit.span();
+        let first = span.start().position();
+        let last = first + span.len();
+        let start = inner.segments.index(first);
+        let old_end = inner.segments.index(last);
+        let replacement = edit.replacement();
+        let new_end = start + replacement.len();
+        let shift = new_end as isize - old_end as isize;
+
+        let mut contents = String::with_capacity(
+            inner.contents.len() - (old_end - start) + replacement.len(),
+        );
+        contents.push_str(&inner.contents[.. start]);
+        contents.push_str(replacement);
+        contents.push_str(&inner.contents[old_end ..]);
+
+        // The boundary before the segment preceding the edit is not affected
+        // by it, and neither are the segments after the first boundary past
+        // the edit that was also a boundary before it. Segmentation resumes
+        // at a character boundary, which bytes may not be.
+        let mut resegmented = first.saturating_sub(1);
+        let mut resegment_from = inner.segments.index(resegmented);
+        while !inner.contents.is_char_boundary(resegment_from) {
+            resegmented -= 1;
+            resegment_from = inner.segments.index(resegmented);
+        }
+        let mut middle = Vec::new();
+        let mut middle_newlines = Vec::new();
+        let mut resumed = previous.len();
+        let boundaries =
+            inner.segmentation.boundaries(&contents[resegment_from ..]);
+        for (index, is_newline) in boundaries {
+            let index = resegment_from + index;
+            if index >= new_end {
+                let old_index = (index as isize - shift) as usize;
+                match inner.segments.binary_search(old_index) {
+                    Ok(position) if position >= last => {
+                        resumed = position;
+                        break;
+                    },
+                    _ => (),
+                }
+            }
+            if is_newline {
+                middle_newlines.push(resegmented + middle.len());
+            }
+            middle.push(index);
+        }
+        let position_shift =
+            (resegmented + middle.len()) as isize - resumed as isize;
+
+        let segments = inner.segments.splice(
+            resegmented .. resumed,
+            middle.iter().copied(),
+            shift,
+        );
+        let newlines_before = match inner.newlines.binary_search(resegmented) {
+            Ok(index) | Err(index) => index,
+        };
+        let newlines_after = match inner.newlines.binary_search(resumed) {
+            Ok(index) | Err(index) => index,
+        };
+        let newlines = inner.newlines.splice(
+            newlines_before .. newlines_after,
+            middle_newlines,
+            position_shift,
+        );
+
+        let source = Source {
+            inner: Arc::new(SourceInner {
+                name: inner.name.clone(),
+                contents: contents.into(),
+                segments,
+                newlines,
+                segmentation: inner.segmentation,
+                encoding: inner.encoding,
+                bom: inner.bom,
+            }),
+        };
+        Self { previous, source, start, old_end, new_end }
+    }
+
+    /// The previous version of the source.
+    pub fn previous(&self) -> &Source {
+        &self.previous
+    }
+
+    /// The new version of the source.
+    pub fn source(&self) -> &Source {
+        &self.source
+    }
+
+    /// Consumes this revision, returning the new version of the source.
+    pub fn into_source(self) -> Source {
+        self.source
+    }
+
+    /// Translates a location in the previous version of the source to the
+    /// new version. Locations before the edit, or at its start, stay where
+    /// they are, and locations after the edit, or at its end, are moved
+    /// along with the contents after it.
+    ///
+    /// Returns `None` if the location belongs to another source, is inside
+    /// the replaced contents, or is no longer at a segment boundary (e.g.
+    /// because a combining accent was inserted after it).
+    pub fn map_location(&self, location: &Location) -> Option<Location> {
+        if *location.source() != self.previous {
+            return None;
+        }
+        let old_index = location.byte_offset();
+        let new_index = if old_index <= self.start {
+            old_index
+        } else if old_index >= self.old_end {
+            old_index - self.old_end + self.new_end
+        } else {
+            return None;
+        };
+        let position =
+            self.source.inner.segments.binary_search(new_index).ok()?;
+        Some(Location::new(self.source.clone(), position))
+    }
+
+    /// Translates a span in the previous version of the source to the new
+    /// version, translating both its start and end as
+    /// [`map_location`](Revision::map_location) does. Spans containing the
+    /// whole edit grow or shrink with it.
+    ///
+    /// Returns `None` if either the start or the end cannot be translated,
+    /// e.g. if the span partially overlaps the replaced contents.
+    pub fn map_span(&self, span: &Span) -> Option<Span> {
+        let start = self.map_location(&span.start())?;
+        let end = self.map_location(&span.end())?;
+        let length = end.position() - start.position();
+        Some(Span::new(start, length))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::source::{Edit, RewriteError, Segmentation, Source};
+
+    fn assert_resegmented(source: &Source) {
+        let fresh = Source::with_segmentation(
+            source.name(),
+            source.contents(),
+            source.segmentation(),
+        );
+        assert_eq!(
+            source.segments().collect::<Vec<_>>(),
+            fresh.segments().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            source.inner.newlines.iter().collect::<Vec<_>>(),
+            fresh.inner.newlines.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn resegment() {
+        let cases = [
+            ("\\x. x\ny", 4, 0, "é\n"),
+            ("cafe\n\nx", 4, 0, "\u{301}"),
+            ("cafe\u{301} x", 4, 1, ""),
+            ("a\r\nb", 1, 1, "\r"),
+            ("a\rb\nc", 2, 0, "\n"),
+            ("\u{1f1e7}\u{1f1f7}\u{1f1e7}\u{1f1f7}x", 0, 1, "\u{1f1e6}"),
+            ("x\ny\nz", 0, 5, ""),
+            ("", 0, 0, "f (g x)\n"),
+            ("f x", 3, 0, "\n\n"),
+            ("é!", 2, 0, "?"),
+            ("é!", 1, 1, ""),
+        ];
+        let segmentations = [
+            Segmentation::ExtendedGraphemes,
+            Segmentation::LegacyGraphemes,
+            Segmentation::Chars,
+            Segmentation::Bytes,
+        ];
+        let mut splits = 0;
+        for &segmentation in &segmentations {
+            for &(contents, start, length, replacement) in &cases {
+                let source =
+                    Source::with_segmentation("a.lam", contents, segmentation);
+                let edit =
+                    Edit::replace(source.span(start, length), replacement);
+                if edit.span().splits_char() {
+                    assert!(matches!(
+                        source.apply_edit(&edit),
+                        Err(RewriteError::SplitChar(_))
+                    ));
+                    assert!(matches!(
+                        source.rewriter().add(edit),
+                        Err(RewriteError::SplitChar(_))
+                    ));
+                    splits += 1;
+                    continue;
+                }
+                let revision = source.apply_edit(&edit).unwrap();
+                let mut rewriter = source.rewriter();
+                rewriter.add(edit).unwrap();
+                assert_eq!(revision.source().contents(), rewriter.finish());
+                assert_resegmented(revision.source());
+            }
+        }
+
+        assert!(splits > 0);
+
+        let other = Source::new("b.lam", "x");
+        let edit = Edit::delete(other.span(0, 1));
+        assert!(matches!(
+            Source::new("c.lam", "x").apply_edit(&edit),
+            Err(RewriteError::ForeignSource(_))
+        ));
+    }
+
+    #[test]
+    fn map_locations() {
+        let source = Source::new("a.lam", "let x = y\nin x");
+        let edit = Edit::replace(source.span(4, 1), "foo");
+        let revision = source.apply_edit(&edit).unwrap();
+        let new = revision.source();
+        assert_eq!(new.contents(), "let foo = y\nin x");
+
+        let mapped = |start, length| {
+            revision
+                .map_span(&source.span(start, length))
+                .map(|span| (span.start().position(), span.len()))
+        };
+        assert_eq!(mapped(0, 3), Some((0, 3)));
+        assert_eq!(mapped(4, 1), Some((4, 3)));
+        assert_eq!(mapped(8, 1), Some((10, 1)));
+        assert_eq!(mapped(13, 1), Some((15, 1)));
+        assert_eq!(mapped(0, 14), Some((0, 16)));
+        assert_eq!(mapped(3, 2), Some((3, 4)));
+        assert_eq!(
+            revision
+                .map_location(&source.span(13, 0).start())
+                .unwrap()
+                .line_column(),
+            (1, 3)
+        );
+
+        let edit = Edit::replace(source.span(2, 5), "");
+        let revision = source.apply_edit(&edit).unwrap();
+        assert_eq!(revision.map_span(&source.span(4, 1)), None);
+        assert_eq!(revision.map_span(&source.span(6, 3)), None);
+
+        let accented = Source::new("b.lam", "cafe x");
+        let edit = Edit::insert(accented.span(4, 0).start(), "\u{301}");
+        let revision = accented.apply_edit(&edit).unwrap();
+        assert_eq!(revision.map_location(&accented.span(4, 0).start()), None);
+        assert_eq!(
+            revision.map_span(&accented.span(5, 1)).unwrap().as_str(),
+            "x"
+        );
+        assert_eq!(revision.map_span(&source.span(0, 1)), None);
+    }
+}
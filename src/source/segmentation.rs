@@ -0,0 +1,196 @@
+//! This module provides the units a source code can be segmented into.
+
+use std::{iter::Enumerate, slice, str::CharIndices};
+use unicode_segmentation::{GraphemeIndices, UnicodeSegmentation};
+
+/// Unit of the string segments of a source code, i.e. what a position, a
+/// [`Reader`](super::Reader) step and a [`Span`](super::Span) length count.
+///
+/// Coarser units are more faithful to what users see as a character, while
+/// finer units are cheaper to segment. Whatever the unit, no memory is spent
+/// on the indices of the leading run of segments that are a single byte
+/// long, so indices are only stored from the first multi-byte segment on, and
+/// never with [`Bytes`](Segmentation::Bytes). A source with a non-ASCII
+/// character near its start pays for the indices of almost every segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Segmentation {
+    /// Extended grapheme clusters, e.g. `e` followed by a combining acute
+    /// accent, or `\r\n`, is a single segment. This is the default.
+    #[default]
+    ExtendedGraphemes,
+    /// Legacy grapheme clusters, which differ from extended ones in that
+    /// spacing marks and prepended characters are segments of their own.
+    LegacyGraphemes,
+    /// Unicode scalar values (`char`s).
+    Chars,
+    /// Raw bytes, meant for ASCII-only or binary-ish formats.
+    ///
+    /// A segment or span splitting a multi-byte character has no string
+    /// contents: indexing the source with it returns `None`, and so does
+    /// [`Reader::current`](super::Reader::current) (see
+    /// [`Reader::current_byte`](super::Reader::current_byte) instead).
+    /// [`Span::as_str`](super::Span::as_str) includes the whole characters a
+    /// span splits, the column methods of [`Location`](super::Location) put a
+    /// location splitting a character in the column of the character, and
+    /// edits splitting a character are rejected with
+    /// [`RewriteError::SplitChar`](super::RewriteError::SplitChar).
+    Bytes,
+}
+
+impl Segmentation {
+    /// Creates an iterator over the start of each segment of the given text,
+    /// in bytes, and whether the segment is a newline.
+    pub(super) fn boundaries(self, text: &str) -> Boundaries<'_> {
+        let inner = match self {
+            Segmentation::ExtendedGraphemes => {
+                BoundariesInner::Graphemes(text.grapheme_indices(true))
+            },
+            Segmentation::LegacyGraphemes => {
+                BoundariesInner::Graphemes(text.grapheme_indices(false))
+            },
+            Segmentation::Chars => BoundariesInner::Chars(text.char_indices()),
+            Segmentation::Bytes => {
+                BoundariesInner::Bytes(text.as_bytes().iter().enumerate())
+            },
+        };
+        Boundaries { inner }
+    }
+}
+
+/// Iterator over the segment boundaries of a text, together with whether
+/// each segment is a newline.
+#[derive(Debug, Clone)]
+pub(super) struct Boundaries<'text> {
+    /// The inner iterator, depending on the unit.
+    inner: BoundariesInner<'text>,
+}
+
+/// Inner iterator of [`Boundaries`].
+#[derive(Debug, Clone)]
+enum BoundariesInner<'text> {
+    /// Boundaries of grapheme clusters.
+    Graphemes(GraphemeIndices<'text>),
+    /// Boundaries of unicode scalar values.
+    Chars(CharIndices<'text>),
+    /// Boundaries of bytes.
+    Bytes(Enumerate<slice::Iter<'text, u8>>),
+}
+
+impl<'text> Iterator for Boundaries<'text> {
+    type Item = (usize, bool);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            BoundariesInner::Graphemes(inner) => inner
+                .next()
+                .map(|(index, grapheme)| (index, grapheme.ends_with('\n'))),
+            BoundariesInner::Chars(inner) => {
+                inner.next().map(|(index, ch)| (index, ch == '\n'))
+            },
+            BoundariesInner::Bytes(inner) => {
+                inner.next().map(|(index, &byte)| (index, byte == b'\n'))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Segmentation;
+    use crate::source::Source;
+
+    #[test]
+    fn units() {
+        let contents = "e\u{301}\nx\u{1f1e7}\u{1f1f7}";
+        let cases = [
+            (Segmentation::ExtendedGraphemes, 4, 2),
+            (Segmentation::LegacyGraphemes, 4, 2),
+            (Segmentation::Chars, 6, 3),
+            (Segmentation::Bytes, 13, 4),
+        ];
+        for &(segmentation, len, position) in &cases {
+            let source =
+                Source::with_segmentation("a.lam", contents, segmentation);
+            assert_eq!(source.segmentation(), segmentation);
+            assert_eq!(source.len(), len);
+            let mut reader = source.reader();
+            while reader.current_byte() != Some(b'x') {
+                reader.next();
+            }
+            assert_eq!(reader.location().position(), position);
+            assert_eq!(reader.location().line_column(), (1, 0));
+        }
+        assert_eq!(Segmentation::default(), Segmentation::ExtendedGraphemes);
+    }
+
+    #[test]
+    fn spacing_marks() {
+        let contents = "\u{915}\u{93f}x";
+        let cases = [
+            (Segmentation::ExtendedGraphemes, 2),
+            (Segmentation::LegacyGraphemes, 3),
+            (Segmentation::Chars, 3),
+            (Segmentation::Bytes, 7),
+        ];
+        for &(segmentation, len) in &cases {
+            let source =
+                Source::with_segmentation("a.lam", contents, segmentation);
+            assert_eq!(source.len(), len);
+            assert_eq!(source.span(len - 1, 1).as_str(), "x");
+            assert_eq!(source.span(len - 1, 1).start().column(), len - 1);
+        }
+    }
+
+    #[test]
+    fn crlf() {
+        let contents = "a\r\nbc\r\nd";
+        let cases = [
+            (Segmentation::ExtendedGraphemes, 3, 5),
+            (Segmentation::LegacyGraphemes, 3, 5),
+            (Segmentation::Chars, 4, 7),
+            (Segmentation::Bytes, 4, 7),
+        ];
+        for &(segmentation, c, d) in &cases {
+            let source =
+                Source::with_segmentation("a.lam", contents, segmentation);
+            assert_eq!(source.line(source.len()), 2);
+            let c = source.span(c, 1);
+            assert_eq!(c.as_str(), "c");
+            assert_eq!(c.start().line_column(), (1, 1));
+            let d = source.span(d, 1);
+            assert_eq!(d.as_str(), "d");
+            assert_eq!(d.start().line_column(), (2, 0));
+        }
+    }
+
+    #[test]
+    fn bytes() {
+        let source =
+            Source::with_segmentation("a.bin", "é!", Segmentation::Bytes);
+        let mut reader = source.reader();
+        assert_eq!(reader.current_byte(), Some(0xc3));
+        assert!(reader.current().is_none());
+        reader.next();
+        assert_eq!(reader.current_byte(), Some(0xa9));
+        assert!(reader.current().is_none());
+        assert!(!reader.is_eof());
+        reader.next();
+        assert_eq!(reader.current_byte(), Some(b'!'));
+        assert_eq!(reader.current().unwrap(), "!");
+        reader.next();
+        assert!(reader.is_eof());
+        assert_eq!(reader.current_byte(), None);
+        assert_eq!(source.get(0 .. 2), Some("é"));
+        assert_eq!(source.get(1 .. 3), None);
+
+        let split = source.span(1, 2);
+        assert!(split.splits_char());
+        assert!(!source.span(0, 2).splits_char());
+        assert_eq!(split.as_str(), "é!");
+        assert_eq!(source.span(0, 1).as_str(), "é");
+        assert_eq!(split.start().char_column(), 0);
+        assert_eq!(split.start().utf16_column(), 0);
+        assert_eq!(split.start().original_byte_offset(), 1);
+        assert_eq!(split.end().char_column(), 2);
+    }
+}
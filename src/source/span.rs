@@ -45,15 +45,34 @@ impl Span {
         self.length
     }
 
+    /// Returns whether this span contains no string segments.
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
     /// The source code object this span refers to.
     pub fn source(&self) -> &Source {
         self.start.source()
     }
 
-    /// Gets the string this span includes as a whole.
+    /// Gets the string this span includes as a whole. If the span splits a
+    /// character (see [`splits_char`](Span::splits_char)), the whole
+    /// character is included.
     pub fn as_str(&self) -> &str {
         let start = self.start.position();
-        self.source().get(start .. start + self.len()).unwrap()
+        let source = self.source();
+        let from = source.floor_char_offset(start);
+        let to = source.ceil_char_offset(start + self.len());
+        &source.contents()[from .. to]
+    }
+
+    /// Returns whether the start or the end of this span splits a character,
+    /// which only happens with
+    /// [`Segmentation::Bytes`](super::Segmentation::Bytes).
+    pub fn splits_char(&self) -> bool {
+        let contents = self.source().contents();
+        !contents.is_char_boundary(self.start.byte_offset())
+            || !contents.is_char_boundary(self.end().byte_offset())
     }
 
     /// Creates a type that, when displayed, shows the span contents, rather
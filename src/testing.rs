@@ -0,0 +1,814 @@
+//! This module provides a harness for snapshot tests of diagnostics: it runs
+//! a pipeline (e.g. lexing and parsing) over a directory of source files, and
+//! compares the diagnostics raised against golden files and against inline
+//! annotations written in the sources. Only available with the `testing`
+//! feature, which is meant to be enabled in `dev-dependencies`.
+//!
+//! # Snapshots
+//!
+//! Diagnostics raised for a file `foo.lam` are sorted by location, rendered by
+//! a [`Renderer`] without colors, and compared against `foo.stderr`. A missing
+//! snapshot is the same as an empty one. Setting the environment variable
+//! [`BLESS_VAR`] (to anything but `0`) updates the snapshots instead, removing
+//! those that would be empty.
+//!
+//! # Annotations
+//!
+//! Like `compiletest`, sources can annotate the diagnostics they expect in
+//! comments:
+//! - `//~ ERROR message` expects an error on the same line;
+//! - `//~^ ERROR message` expects it on the line above (one line per `^`);
+//! - `//~| ERROR message` expects it on the line of the previous annotation;
+//! - `//~? ERROR message` expects it without a primary span.
+//!
+//! Kinds are `BUG`, `ERROR`, `WARNING` (or `WARN`), `NOTE` and `HELP`, and
+//! the message must be a substring of the actual one. Kinds match
+//! diagnostics with the given effective level, while `NOTE` and `HELP` also
+//! match children. Each diagnostic must be annotated, but notes
+//! and help children may go unannotated. Sources without annotations are only
+//! checked against their snapshots.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use bittongue::{diagnostic::Diagnostics, testing::Harness};
+//!
+//! let mut harness = Harness::new("tests/ui");
+//! harness.set_extension("lam");
+//! harness.run(|source, diagnostics: &mut Diagnostics| {
+//!     // Lex, parse and check `source`, raising into `diagnostics`.
+//! });
+//! ```
+
+use crate::{
+    diagnostic::{ChildKind, Diagnostic, Diagnostics, Level, Renderer},
+    source::{Source, Span},
+};
+use std::{
+    env,
+    error::Error,
+    fmt,
+    fs,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Environment variable that, when set to anything but `0` or the empty
+/// string, makes [`Harness`] update snapshots instead of comparing them.
+pub const BLESS_VAR: &str = "BITTONGUE_BLESS";
+
+/// Extension of snapshot files.
+pub const SNAPSHOT_EXTENSION: &str = "stderr";
+
+/// Kind of diagnostic expected by an [`Annotation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AnnotationKind {
+    /// `ERROR`: a diagnostic with [`Level::Error`].
+    Error,
+    /// `WARNING` or `WARN`: a diagnostic with [`Level::Warning`].
+    Warning,
+    /// `NOTE`: a diagnostic with [`Level::Note`], or a note child.
+    Note,
+    /// `HELP`: a diagnostic with [`Level::Help`], or a help child.
+    Help,
+    /// `BUG`: a diagnostic with [`Level::Bug`].
+    Bug,
+}
+
+impl AnnotationKind {
+    /// Parses the keyword of an annotation kind, e.g. `ERROR`.
+    fn parse(keyword: &str) -> Option<Self> {
+        match keyword {
+            "ERROR" => Some(AnnotationKind::Error),
+            "WARNING" | "WARN" => Some(AnnotationKind::Warning),
+            "NOTE" => Some(AnnotationKind::Note),
+            "HELP" => Some(AnnotationKind::Help),
+            "BUG" => Some(AnnotationKind::Bug),
+            _ => None,
+        }
+    }
+
+    /// Returns whether a diagnostic with the given effective level matches
+    /// this kind.
+    fn matches_level(self, level: Level) -> bool {
+        match self {
+            AnnotationKind::Error => level == Level::Error,
+            AnnotationKind::Warning => level == Level::Warning,
+            AnnotationKind::Note => level == Level::Note,
+            AnnotationKind::Help => level == Level::Help,
+            AnnotationKind::Bug => level == Level::Bug,
+        }
+    }
+
+    /// Returns whether a child of the given kind matches this kind.
+    fn matches_child(self, kind: ChildKind) -> bool {
+        match self {
+            AnnotationKind::Note => kind == ChildKind::Note,
+            AnnotationKind::Help => kind == ChildKind::Help,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for AnnotationKind {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        fmtr.write_str(match self {
+            AnnotationKind::Error => "ERROR",
+            AnnotationKind::Warning => "WARNING",
+            AnnotationKind::Note => "NOTE",
+            AnnotationKind::Help => "HELP",
+            AnnotationKind::Bug => "BUG",
+        })
+    }
+}
+
+/// A diagnostic expected by an inline annotation, such as
+/// `//~^ ERROR unmatched`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Annotation {
+    /// Line expected for the diagnostic, starting from `0`, or `None` if no
+    /// primary span is expected.
+    pub line: Option<usize>,
+    /// Kind of the diagnostic expected.
+    pub kind: AnnotationKind,
+    /// Substring of the message expected.
+    pub message: String,
+}
+
+impl Annotation {
+    /// Parses the annotations of the given source, written after the given
+    /// marker (usually `//~`).
+    pub fn parse_all(
+        source: &Source,
+        marker: &str,
+    ) -> Result<Vec<Self>, InvalidAnnotation> {
+        let line_count = source.line(source.len()) + 1;
+        let mut annotations = Vec::<Self>::new();
+        for line in 0 .. line_count {
+            let text =
+                &source[source.line_start(line) .. source.line_end(line)];
+            let invalid = || InvalidAnnotation { line, text: text.to_owned() };
+            let rest = match text.find(marker) {
+                Some(index) => &text[index + marker.len() ..],
+                None => continue,
+            };
+            let (target, rest) = if let Some(rest) = rest.strip_prefix('?') {
+                (None, rest)
+            } else if let Some(rest) = rest.strip_prefix('|') {
+                let previous = annotations.last().ok_or_else(invalid)?;
+                (previous.line, rest)
+            } else {
+                let carets = rest.len() - rest.trim_start_matches('^').len();
+                let target = line.checked_sub(carets).ok_or_else(invalid)?;
+                (Some(target), &rest[carets ..])
+            };
+            let rest = rest.trim();
+            let (keyword, message) = rest.split_once(' ').unwrap_or((rest, ""));
+            let kind = AnnotationKind::parse(keyword).ok_or_else(invalid)?;
+            annotations.push(Self {
+                line: target,
+                kind,
+                message: message.trim().to_owned(),
+            });
+        }
+        Ok(annotations)
+    }
+}
+
+impl fmt::Display for Annotation {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(fmtr, "line {}: ", line + 1)?,
+            None => write!(fmtr, "no location: ")?,
+        }
+        write!(fmtr, "{}", self.kind)?;
+        if !self.message.is_empty() {
+            write!(fmtr, " {}", self.message)?;
+        }
+        Ok(())
+    }
+}
+
+/// Error returned when an annotation cannot be parsed, e.g. it has an unknown
+/// kind or points above the first line.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InvalidAnnotation {
+    /// Line of the annotation, starting from `0`.
+    pub line: usize,
+    /// Text of the line.
+    pub text: String,
+}
+
+impl fmt::Display for InvalidAnnotation {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmtr,
+            "invalid annotation at line {}: {}",
+            self.line + 1,
+            self.text
+        )
+    }
+}
+
+impl Error for InvalidAnnotation {}
+
+/// A failed check of a [`Harness`].
+#[derive(Debug)]
+pub enum Failure {
+    /// A file could not be read or written.
+    Io {
+        /// Path of the file.
+        path: PathBuf,
+        /// Error that occurred.
+        error: io::Error,
+    },
+    /// A source has an annotation that cannot be parsed.
+    Invalid {
+        /// Path of the source.
+        path: PathBuf,
+        /// Error that occurred.
+        error: InvalidAnnotation,
+    },
+    /// The rendered diagnostics differ from the snapshot.
+    Snapshot {
+        /// Path of the snapshot.
+        path: PathBuf,
+        /// Contents of the snapshot.
+        expected: String,
+        /// Diagnostics actually rendered.
+        actual: String,
+    },
+    /// An annotation matched no diagnostic.
+    Unmatched {
+        /// Path of the source.
+        path: PathBuf,
+        /// The annotation.
+        annotation: Annotation,
+    },
+    /// A diagnostic matched no annotation.
+    Unexpected {
+        /// Path of the source.
+        path: PathBuf,
+        /// Line of the diagnostic, starting from `0`, if it has a primary span
+        /// in the source.
+        line: Option<usize>,
+        /// Effective level of the diagnostic.
+        level: Level,
+        /// Message of the diagnostic.
+        message: String,
+    },
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Failure::Io { path, error } => {
+                write!(fmtr, "{}: {}", path.display(), error)
+            },
+            Failure::Invalid { path, error } => {
+                write!(fmtr, "{}: {}", path.display(), error)
+            },
+            Failure::Snapshot { path, expected, actual } => {
+                writeln!(fmtr, "{}: snapshot mismatch", path.display())?;
+                write_diff(fmtr, expected, actual)?;
+                write!(fmtr, "(set {}=1 to update snapshots)", BLESS_VAR)
+            },
+            Failure::Unmatched { path, annotation } => {
+                write!(fmtr, "{}: expected {}", path.display(), annotation)
+            },
+            Failure::Unexpected { path, line, level, message } => {
+                write!(fmtr, "{}: unexpected ", path.display())?;
+                match line {
+                    Some(line) => {
+                        write!(fmtr, "{} at line {}", level, line + 1)?
+                    },
+                    None => write!(fmtr, "{} without location", level)?,
+                }
+                write!(fmtr, ": {}", message)
+            },
+        }
+    }
+}
+
+impl Error for Failure {}
+
+/// Runs a pipeline over a directory of sources, checking the diagnostics
+/// raised against snapshots and inline annotations. See the
+/// [module documentation](self) for details.
+#[derive(Debug, Clone)]
+pub struct Harness {
+    /// Directory of the sources, searched recursively.
+    root: PathBuf,
+    /// Extension of the sources, without the dot.
+    extension: String,
+    /// Marker starting annotations.
+    marker: String,
+    /// Renderer of snapshots.
+    renderer: Renderer,
+    /// Whether snapshots are updated instead of compared.
+    bless: bool,
+}
+
+impl Harness {
+    /// Creates a harness over the given directory. Sources are files with
+    /// any extension but `stderr`, annotations start with `//~`, and
+    /// snapshots are updated if [`BLESS_VAR`] is set.
+    pub fn new<P>(root: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        let bless = env::var_os(BLESS_VAR)
+            .is_some_and(|value| !value.is_empty() && value != "0");
+        Self {
+            root: root.into(),
+            extension: String::new(),
+            marker: "//~".to_owned(),
+            renderer: Renderer::new(),
+            bless,
+        }
+    }
+
+    /// Directory of the sources.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Extension of the sources, without the dot. Empty if any extension is
+    /// accepted.
+    pub fn extension(&self) -> &str {
+        &self.extension
+    }
+
+    /// Sets the extension of the sources, without the dot, e.g. `lam`. If
+    /// empty, any file but snapshots is a source.
+    pub fn set_extension<S>(&mut self, extension: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.extension = extension.into();
+        self
+    }
+
+    /// Marker starting annotations.
+    pub fn marker(&self) -> &str {
+        &self.marker
+    }
+
+    /// Sets the marker starting annotations, e.g. `;~` for a language whose
+    /// comments start with `;`.
+    pub fn set_marker<S>(&mut self, marker: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.marker = marker.into();
+        self
+    }
+
+    /// Renderer of snapshots.
+    pub fn renderer(&self) -> &Renderer {
+        &self.renderer
+    }
+
+    /// Sets the renderer of snapshots. Colors should be disabled, so
+    /// snapshots stay readable.
+    pub fn set_renderer(&mut self, renderer: Renderer) -> &mut Self {
+        self.renderer = renderer;
+        self
+    }
+
+    /// Returns whether snapshots are updated instead of compared.
+    pub fn is_blessing(&self) -> bool {
+        self.bless
+    }
+
+    /// Sets whether snapshots are updated instead of compared, overriding
+    /// [`BLESS_VAR`].
+    pub fn set_bless(&mut self, bless: bool) -> &mut Self {
+        self.bless = bless;
+        self
+    }
+
+    /// Runs the given pipeline over every source and checks the results,
+    /// panicking with a report of every failure, if any.
+    pub fn run<F, D>(&self, pipeline: F)
+    where
+        F: FnMut(&Source, &mut Diagnostics<D>),
+        D: Diagnostic + ?Sized,
+    {
+        let failures = self.check(pipeline);
+        if !failures.is_empty() {
+            let mut report = String::new();
+            for failure in &failures {
+                report.push_str(&failure.to_string());
+                report.push_str("\n\n");
+            }
+            panic!(
+                "{} snapshot check(s) failed:\n\n{}",
+                failures.len(),
+                report
+            );
+        }
+    }
+
+    /// Runs the given pipeline over every source and checks the results,
+    /// returning the failures. Sources are named by their paths relative to
+    /// the root, with `/` as separator, and visited in order of path.
+    pub fn check<F, D>(&self, mut pipeline: F) -> Vec<Failure>
+    where
+        F: FnMut(&Source, &mut Diagnostics<D>),
+        D: Diagnostic + ?Sized,
+    {
+        let mut paths = Vec::new();
+        let mut failures = Vec::new();
+        if let Err(error) = self.collect_paths(&self.root, &mut paths) {
+            failures.push(Failure::Io { path: self.root.clone(), error });
+        }
+        paths.sort();
+        for path in paths {
+            self.check_path(&path, &mut pipeline, &mut failures);
+        }
+        failures
+    }
+
+    /// Collects the paths of sources in the given directory, recursively.
+    fn collect_paths(
+        &self,
+        directory: &Path,
+        paths: &mut Vec<PathBuf>,
+    ) -> io::Result<()> {
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                self.collect_paths(&path, paths)?;
+            } else if self.is_source(&path) {
+                paths.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns whether the given file is a source.
+    fn is_source(&self, path: &Path) -> bool {
+        let extension =
+            path.extension().and_then(|extension| extension.to_str());
+        if self.extension.is_empty() {
+            extension != Some(SNAPSHOT_EXTENSION)
+        } else {
+            extension == Some(&*self.extension)
+        }
+    }
+
+    /// Runs the pipeline over a single source and checks the results.
+    fn check_path<F, D>(
+        &self,
+        path: &Path,
+        pipeline: &mut F,
+        failures: &mut Vec<Failure>,
+    ) where
+        F: FnMut(&Source, &mut Diagnostics<D>),
+        D: Diagnostic + ?Sized,
+    {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) => {
+                failures.push(Failure::Io { path: path.to_owned(), error });
+                return;
+            },
+        };
+        let source = Source::new(self.source_name(path), contents);
+        let mut diagnostics = Diagnostics::new();
+        pipeline(&source, &mut diagnostics);
+        diagnostics.sort();
+
+        match Annotation::parse_all(&source, &self.marker) {
+            Ok(annotations) if annotations.is_empty() => (),
+            Ok(annotations) => check_annotations(
+                path,
+                &source,
+                &diagnostics,
+                annotations,
+                failures,
+            ),
+            Err(error) => {
+                failures.push(Failure::Invalid { path: path.to_owned(), error })
+            },
+        }
+
+        let mut actual = String::new();
+        self.renderer
+            .write_all(&mut actual, &diagnostics)
+            .expect("writing into a string cannot fail");
+        let snapshot = path.with_extension(SNAPSHOT_EXTENSION);
+        let result = if self.bless {
+            self.bless_snapshot(&snapshot, &actual)
+        } else {
+            compare_snapshot(&snapshot, actual)
+        };
+        if let Err(failure) = result {
+            failures.push(failure);
+        }
+    }
+
+    /// Name of a source: its path relative to the root, separated by `/`.
+    fn source_name(&self, path: &Path) -> String {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        let components = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>();
+        components.join("/")
+    }
+
+    /// Writes the given snapshot, or removes it if empty.
+    fn bless_snapshot(&self, path: &Path, actual: &str) -> Result<(), Failure> {
+        let result = if !actual.is_empty() {
+            fs::write(path, actual)
+        } else if path.exists() {
+            fs::remove_file(path)
+        } else {
+            Ok(())
+        };
+        result.map_err(|error| Failure::Io { path: path.to_owned(), error })
+    }
+}
+
+/// Compares the given snapshot against the actual rendered diagnostics.
+fn compare_snapshot(path: &Path, actual: String) -> Result<(), Failure> {
+    let expected = match fs::read_to_string(path) {
+        Ok(expected) => expected.replace("\r\n", "\n"),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(error) => return Err(Failure::Io { path: path.to_owned(), error }),
+    };
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(Failure::Snapshot { path: path.to_owned(), expected, actual })
+    }
+}
+
+/// Matches annotations against the diagnostics of a source, reporting
+/// unmatched annotations and unexpected diagnostics.
+fn check_annotations<D>(
+    path: &Path,
+    source: &Source,
+    diagnostics: &Diagnostics<D>,
+    annotations: Vec<Annotation>,
+    failures: &mut Vec<Failure>,
+) where
+    D: Diagnostic + ?Sized,
+{
+    let line_of = |span: Option<Span>| {
+        span.filter(|span| span.source() == source)
+            .map(|span| span.start().line())
+    };
+    let actual = diagnostics
+        .iter_leveled()
+        .map(|(level, diagnostic)| {
+            let line = line_of(diagnostic.primary_span());
+            let children = diagnostic
+                .children()
+                .map(|child| {
+                    let line = line_of(child.span).or(line);
+                    (line, child.kind, child.message)
+                })
+                .collect::<Vec<_>>();
+            (line, level, diagnostic.to_string(), children)
+        })
+        .collect::<Vec<_>>();
+    let mut matched = vec![false; actual.len()];
+    let mut matched_children = actual
+        .iter()
+        .map(|entry| vec![false; entry.3.len()])
+        .collect::<Vec<_>>();
+
+    for annotation in annotations {
+        let found = actual.iter().enumerate().position(|(i, entry)| {
+            let (line, level, message, _) = entry;
+            !matched[i]
+                && *line == annotation.line
+                && annotation.kind.matches_level(*level)
+                && message.contains(&annotation.message)
+        });
+        if let Some(i) = found {
+            matched[i] = true;
+            continue;
+        }
+        let found = actual.iter().enumerate().find_map(|(i, entry)| {
+            let position = entry.3.iter().enumerate().position(|(j, child)| {
+                let (line, kind, message) = child;
+                !matched_children[i][j]
+                    && *line == annotation.line
+                    && annotation.kind.matches_child(*kind)
+                    && message.contains(&annotation.message)
+            });
+            position.map(|j| (i, j))
+        });
+        match found {
+            Some((i, j)) => matched_children[i][j] = true,
+            None => failures
+                .push(Failure::Unmatched { path: path.to_owned(), annotation }),
+        }
+    }
+
+    for ((line, level, message, _), matched) in actual.into_iter().zip(matched)
+    {
+        if !matched {
+            failures.push(Failure::Unexpected {
+                path: path.to_owned(),
+                line,
+                level,
+                message,
+            });
+        }
+    }
+}
+
+/// Writes a line diff from the expected text to the actual one, prefixing
+/// removed lines with `-`, added lines with `+` and common lines with a space.
+fn write_diff(
+    fmtr: &mut fmt::Formatter,
+    expected: &str,
+    actual: &str,
+) -> fmt::Result {
+    let expected = expected.lines().collect::<Vec<_>>();
+    let actual = actual.lines().collect::<Vec<_>>();
+    // Length of the longest common subsequence of each pair of suffixes.
+    let mut common = vec![vec![0; actual.len() + 1]; expected.len() + 1];
+    for i in (0 .. expected.len()).rev() {
+        for j in (0 .. actual.len()).rev() {
+            common[i][j] = if expected[i] == actual[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            writeln!(fmtr, " {}", expected[i])?;
+            i += 1;
+            j += 1;
+        } else if j < actual.len()
+            && (i == expected.len() || common[i][j + 1] >= common[i + 1][j])
+        {
+            writeln!(fmtr, "+{}", actual[j])?;
+            j += 1;
+        } else {
+            writeln!(fmtr, "-{}", expected[i])?;
+            i += 1;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Annotation, AnnotationKind, Failure, Harness};
+    use crate::{
+        diagnostic::{Child, Diagnostic, Diagnostics, Level},
+        source::{Source, Span},
+    };
+    use std::{env, fmt, fs, path::PathBuf, process};
+
+    #[derive(Debug)]
+    struct Unexpected {
+        span: Span,
+    }
+
+    impl fmt::Display for Unexpected {
+        fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmtr, "unexpected `{}`", self.span.as_str())
+        }
+    }
+
+    impl Diagnostic for Unexpected {
+        fn level(&self) -> Level {
+            Level::Error
+        }
+
+        fn primary_span(&self) -> Option<Span> {
+            Some(self.span.clone())
+        }
+
+        fn children<'this>(
+            &'this self,
+        ) -> Box<dyn Iterator<Item = Child> + Send + Sync + 'this> {
+            Box::new(Some(Child::help("remove it")).into_iter())
+        }
+    }
+
+    /// Raises an error for every `!` in the source.
+    fn pipeline(source: &Source, diagnostics: &mut Diagnostics) {
+        let mut reader = source.reader();
+        while !reader.is_eof() {
+            reader.mark();
+            let bang = reader.test(|grapheme| grapheme == "!");
+            reader.next();
+            if bang {
+                diagnostics.raise(Unexpected { span: reader.span() });
+            }
+        }
+    }
+
+    fn directory(name: &str) -> PathBuf {
+        let directory = env::temp_dir().join(format!(
+            "bittongue-testing-{}-{}",
+            name,
+            process::id()
+        ));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(directory.join("nested")).unwrap();
+        directory
+    }
+
+    #[test]
+    fn parse_annotations() {
+        let source = Source::new(
+            "main.lam",
+            "a //~ ERROR first\nb\n//~^ WARN second\n//~| HELP\n//~? NOTE x y",
+        );
+        let annotations = Annotation::parse_all(&source, "//~").unwrap();
+        let expected = [
+            (Some(0), AnnotationKind::Error, "first"),
+            (Some(1), AnnotationKind::Warning, "second"),
+            (Some(1), AnnotationKind::Help, ""),
+            (None, AnnotationKind::Note, "x y"),
+        ];
+        let annotations = annotations
+            .iter()
+            .map(|entry| (entry.line, entry.kind, &*entry.message))
+            .collect::<Vec<_>>();
+        assert_eq!(annotations, expected);
+
+        let source = Source::new("main.lam", "//~^ ERROR above");
+        assert!(Annotation::parse_all(&source, "//~").is_err());
+        let source = Source::new("main.lam", "a //~ FATAL x");
+        assert!(Annotation::parse_all(&source, "//~").is_err());
+    }
+
+    #[test]
+    fn bless_and_compare() {
+        let directory = directory("snapshots");
+        fs::write(directory.join("ok.lam"), "fine").unwrap();
+        fs::write(directory.join("nested/bad.lam"), "a!").unwrap();
+        let mut harness = Harness::new(&directory);
+        harness.set_extension("lam").set_bless(true);
+        assert!(harness.check(pipeline).is_empty());
+        assert!(!directory.join("ok.stderr").exists());
+        let snapshot =
+            fs::read_to_string(directory.join("nested/bad.stderr")).unwrap();
+        assert_eq!(
+            snapshot,
+            "error: unexpected `!`\n --> nested/bad.lam:1:2\n  |\n1 | a!\n  \
+             |  ^\n  = help: remove it\n"
+        );
+
+        harness.set_bless(false);
+        assert!(harness.check(pipeline).is_empty());
+        fs::write(directory.join("ok.lam"), "!").unwrap();
+        let failures = harness.check(pipeline);
+        assert_eq!(failures.len(), 1);
+        match &failures[0] {
+            Failure::Snapshot { path, expected, .. } => {
+                assert_eq!(path, &directory.join("ok.stderr"));
+                assert_eq!(expected, "");
+            },
+            failure => panic!("unexpected failure {}", failure),
+        }
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn annotations() {
+        let directory = directory("annotations");
+        fs::write(
+            directory.join("main.lam"),
+            "! //~ ERROR unexpected\n//~| HELP remove\n\n!\n!\n//~^ ERROR \
+             unexpected\n//~? ERROR missing",
+        )
+        .unwrap();
+        let mut harness = Harness::new(&directory);
+        harness.set_bless(true);
+        let failures = harness
+            .check(pipeline)
+            .iter()
+            .map(Failure::to_string)
+            .collect::<Vec<_>>();
+        let path = directory.join("main.lam");
+        assert_eq!(
+            failures,
+            [
+                format!(
+                    "{}: expected no location: ERROR missing",
+                    path.display()
+                ),
+                format!(
+                    "{}: unexpected error at line 4: unexpected `!`",
+                    path.display()
+                ),
+            ]
+        );
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,146 @@
+use bittongue::{
+    diagnostic::{ChildKind, Diagnostic, Level},
+    source::{Source, Span},
+};
+
+#[derive(Debug, bittongue_derive::Diagnostic)]
+#[diagnostic(error, code = "E0003", message = "unmatched `{open}` ({{}})")]
+struct Unmatched {
+    #[primary(label = "`{open}` opened here")]
+    open: Span,
+    #[secondary(label = "expected `)`")]
+    #[help = "close `{open}` here"]
+    found: Option<Span>,
+    #[note]
+    context: Option<String>,
+}
+
+#[derive(Debug, bittongue_derive::Diagnostic)]
+#[diagnostic(warning, message = "{count:?} unused names")]
+struct Unused<T>
+where
+    T: std::fmt::Debug + Send + Sync + 'static,
+{
+    #[secondary]
+    names: Vec<Span>,
+    count: T,
+}
+
+#[derive(Debug, bittongue_derive::Diagnostic)]
+#[diagnostic(note, message = "nothing to do")]
+struct Empty;
+
+#[derive(Debug, bittongue_derive::Diagnostic)]
+#[diagnostic(help, message = "consider `{name}`")]
+struct Suggest {
+    name: String,
+}
+
+#[derive(Debug, bittongue_derive::Diagnostic)]
+#[diagnostic(bug, code = "ICE", message = "unreachable state")]
+struct Unreachable {
+    #[primary]
+    at: Span,
+}
+
+fn span(source: &Source, start: usize, length: usize) -> Span {
+    let mut reader = source.reader();
+    reader.advance(start);
+    reader.mark();
+    reader.advance(length);
+    reader.span()
+}
+
+#[test]
+fn spans_and_labels() {
+    let source = Source::new("main.lam", "(x y");
+    let diagnostic = Unmatched {
+        open: span(&source, 0, 1),
+        found: Some(span(&source, 3, 1)),
+        context: None,
+    };
+    assert_eq!(diagnostic.level(), Level::Error);
+    assert_eq!(diagnostic.code(), Some("E0003"));
+    assert_eq!(diagnostic.to_string(), "unmatched `(` ({})");
+    assert_eq!(diagnostic.primary_span(), Some(span(&source, 0, 1)));
+    let secondary =
+        diagnostic.secondary_spans().into_iter().flatten().collect::<Vec<_>>();
+    assert_eq!(secondary, [span(&source, 3, 1)]);
+    let labels = diagnostic
+        .labels()
+        .map(|label| {
+            (label.is_primary(), label.span.as_str().to_owned(), label.message)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        labels,
+        [
+            (true, "(".to_owned(), Some("`(` opened here".to_owned())),
+            (false, "y".to_owned(), Some("expected `)`".to_owned())),
+        ]
+    );
+}
+
+#[test]
+fn children() {
+    let source = Source::new("main.lam", "(x y");
+    let diagnostic = Unmatched {
+        open: span(&source, 0, 1),
+        found: Some(span(&source, 3, 1)),
+        context: Some("in a lambda body".to_owned()),
+    };
+    let children = diagnostic
+        .children()
+        .map(|child| (child.kind, child.message, child.span))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        children,
+        [
+            (
+                ChildKind::Help,
+                "close `(` here".to_owned(),
+                Some(span(&source, 3, 1)),
+            ),
+            (ChildKind::Note, "in a lambda body".to_owned(), None),
+        ]
+    );
+    let without =
+        Unmatched { open: span(&source, 0, 1), found: None, context: None };
+    assert_eq!(without.children().count(), 0);
+    assert_eq!(without.labels().count(), 1);
+}
+
+#[test]
+fn generics_and_defaults() {
+    let source = Source::new("main.lam", "a b");
+    let diagnostic = Unused {
+        names: vec![span(&source, 0, 1), span(&source, 2, 1)],
+        count: 2,
+    };
+    assert_eq!(diagnostic.level(), Level::Warning);
+    assert_eq!(diagnostic.code(), None);
+    assert_eq!(diagnostic.to_string(), "2 unused names");
+    assert_eq!(diagnostic.primary_span(), None);
+    assert_eq!(
+        diagnostic.labels().filter(|label| !label.is_primary()).count(),
+        2
+    );
+
+    assert_eq!(Empty.level(), Level::Note);
+    assert_eq!(Empty.to_string(), "nothing to do");
+    assert_eq!(Empty.labels().count(), 0);
+    assert_eq!(Empty.children().count(), 0);
+}
+
+#[test]
+fn help_and_bug_levels() {
+    let suggest = Suggest { name: "foo".to_owned() };
+    assert_eq!(suggest.level(), Level::Help);
+    assert_eq!(suggest.to_string(), "consider `foo`");
+
+    let source = Source::new("main.lam", "x");
+    let unreachable = Unreachable { at: span(&source, 0, 1) };
+    assert_eq!(unreachable.level(), Level::Bug);
+    assert_eq!(unreachable.code(), Some("ICE"));
+    assert_eq!(unreachable.primary_span(), Some(span(&source, 0, 1)));
+}